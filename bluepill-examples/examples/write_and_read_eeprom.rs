@@ -8,10 +8,10 @@
 
 use cortex_m_rt::entry;
 use cortex_m_semihosting::hprintln;
-use hal::i2c::{BlockingI2c, Mode};
+use hal::i2c::Mode;
 use hal::pac;
 use hal::prelude::*;
-use hal::time::U32Ext;
+use hal::rcc::Config;
 
 use mcp4725::*;
 use panic_semihosting as _;
@@ -19,29 +19,24 @@ use panic_semihosting as _;
 #[entry]
 fn main() -> ! {
     let dp = pac::Peripherals::take().unwrap();
+    let mut cp = cortex_m::Peripherals::take().unwrap();
 
     // Configure the clock
-    let mut rcc = dp.RCC.constrain();
     let mut flash = dp.FLASH.constrain();
-    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+    let mut rcc = dp.RCC.constrain().freeze(Config::default(), &mut flash.acr);
 
-    let mut gpiob = dp.GPIOB.split(&mut rcc.apb2);
-    let mut afio = dp.AFIO.constrain(&mut rcc.apb2);
+    let gpiob = dp.GPIOB.split(&mut rcc);
+    let mut afio = dp.AFIO.constrain(&mut rcc);
 
-    // Configure the pins for I2C1
-    let scl = gpiob.pb8.into_alternate_open_drain(&mut gpiob.crh);
-    let sda = gpiob.pb9.into_alternate_open_drain(&mut gpiob.crh);
+    // The blocking I2C driver uses the cycle counter for its timeouts
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
 
-    // Configure I2C
-    let i2c = BlockingI2c::i2c1(
-        dp.I2C1,
-        (scl, sda),
-        &mut afio.mapr,
-        Mode::Standard {
-            frequency: 400000.hz(),
-        },
-        clocks,
-        &mut rcc.apb1,
+    // Configure I2C1 on the remapped pins pb8 and pb9
+    let i2c = dp.I2C1.remap(&mut afio.mapr).blocking_i2c(
+        (gpiob.pb8, gpiob.pb9),
+        Mode::standard(400.kHz()),
+        &mut rcc,
         1000,
         10,
         1000,
@@ -51,17 +46,17 @@ fn main() -> ! {
     // Configure the MCP4725 DAC
     let mut dac = MCP4725::new(i2c, 0b010);
 
-    hprintln!("old status {:x?}", dac.read().unwrap()).ok();
+    hprintln!("old status {:x?}", dac.read().unwrap());
 
     // Set the output
     dac.set_dac_and_eeprom(PowerDown::Resistor100kOhm, 0x0112)
         .ok();
 
     // This probably prints eeprom still writing
-    hprintln!("new status {:x?}", dac.read().unwrap()).ok();
+    hprintln!("new status {:x?}", dac.read().unwrap());
 
     // This prints eeprom done writing
-    hprintln!("new new status {:x?}", dac.read().unwrap()).ok();
+    hprintln!("new new status {:x?}", dac.read().unwrap());
 
     loop {
         continue;
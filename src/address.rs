@@ -0,0 +1,67 @@
+//! Type-safe representation of the MCP4725 user address bits
+
+/// The factory variant of the MCP4725. Each variant has different factory set A2 and A1 address
+/// bits, the variant is marked on the packaging.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Variant {
+    /// MCP4725A0, A2 = 0, A1 = 0
+    MCP4725A0 = 0b00,
+    /// MCP4725A1, A2 = 0, A1 = 1
+    MCP4725A1 = 0b01,
+    /// MCP4725A2, A2 = 1, A1 = 0
+    MCP4725A2 = 0b10,
+    /// MCP4725A3, A2 = 1, A1 = 1
+    MCP4725A3 = 0b11,
+}
+
+/// The user part of the MCP4725 address. Made up of the factory variant that determines the A2 and
+/// A1 bits and the state of the A0 pin.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Address {
+    /// The factory variant of the chip
+    pub variant: Variant,
+    /// true if the A0 pin is pulled high, false if it is pulled low
+    pub a0: bool,
+}
+
+impl Address {
+    /// Construct an address from the chip variant and the state of the A0 pin
+    pub fn new(variant: Variant, a0: bool) -> Self {
+        Address { variant, a0 }
+    }
+
+    /// The three bit user address (A2, A1 and A0) for this address
+    pub fn user_address(&self) -> u8 {
+        (self.variant as u8) << 1 | self.a0 as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_encode_a0_pin_into_lowest_bit() {
+        assert_eq!(
+            Address::new(Variant::MCP4725A0, false).user_address(),
+            0b000
+        );
+        assert_eq!(Address::new(Variant::MCP4725A0, true).user_address(), 0b001);
+    }
+
+    #[test]
+    fn should_encode_variant_into_upper_bits() {
+        assert_eq!(
+            Address::new(Variant::MCP4725A1, false).user_address(),
+            0b010
+        );
+        assert_eq!(
+            Address::new(Variant::MCP4725A2, false).user_address(),
+            0b100
+        );
+        assert_eq!(Address::new(Variant::MCP4725A3, true).user_address(), 0b111);
+    }
+}
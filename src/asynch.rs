@@ -0,0 +1,143 @@
+//! Async MCP4725 driver built on the embedded-hal-async `I2c` trait. Mirrors the blocking
+//! `MCP4725` driver API and shares its command encoding.
+//! ```
+//! # use mcp4725::*;
+//! # use mcp4725::asynch::AsyncMCP4725;
+//! # async fn example<I2C: embedded_hal_async::i2c::I2c>(i2c: I2C) {
+//! let mut dac = AsyncMCP4725::new(i2c, 0b010);
+//! dac.set_dac(PowerDown::Normal, 0x0fff).await.unwrap();
+//! # }
+//! ```
+
+use crate::encode::{encode_address, encode_command, encode_fast_command};
+use crate::{CommandType, DacStatus, PowerDown};
+use embedded_hal_async::i2c::I2c;
+
+/// Async MCP4725 DAC driver. Wraps an async I2C port to send commands to an MCP4725
+#[derive(Debug)]
+pub struct AsyncMCP4725<I2C>
+where
+    I2C: I2c,
+{
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C, E> AsyncMCP4725<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Construct a new async MCP4725 driver instance.
+    /// i2c is the initialized async i2c driver port to use,
+    /// user_address is the three bit user-part of the i2c address where the MCP4725 can be reached,
+    /// see `MCP4725::new` for details.
+    pub fn new(i2c: I2C, user_address: u8) -> Self {
+        AsyncMCP4725 {
+            i2c,
+            address: encode_address(user_address),
+        }
+    }
+
+    /// Set the dac register
+    pub async fn set_dac(&mut self, power: PowerDown, data: u16) -> Result<(), E> {
+        let bytes = encode_command(CommandType::WriteDac, power, data);
+        self.i2c.write(self.address, &bytes).await
+    }
+
+    /// Set the dac and eeprom registers
+    pub async fn set_dac_and_eeprom(&mut self, power: PowerDown, data: u16) -> Result<(), E> {
+        let bytes = encode_command(CommandType::WriteDacAndEEPROM, power, data);
+        self.i2c.write(self.address, &bytes).await
+    }
+
+    /// Use the two byte fast command to set the dac register
+    pub async fn set_dac_fast(&mut self, power: PowerDown, data: u16) -> Result<(), E> {
+        let bytes = encode_fast_command(power, data);
+        self.i2c.write(self.address, &bytes).await
+    }
+
+    /// Send read command and return the dac status
+    pub async fn read(&mut self) -> Result<DacStatus, E> {
+        let mut buffer: [u8; 5] = [0; 5];
+        self.i2c.read(self.address, &mut buffer).await?;
+
+        Ok(buffer.into())
+    }
+
+    /// The 7 bit I2C address the driver uses to reach the MCP4725
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Destroy the async MCP4725 driver, return the wrapped I2C
+    pub fn destroy(self) -> I2C {
+        self.i2c
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::RecordingI2c;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    extern crate std;
+    use std::vec;
+
+    /// Poll a future that completes without waiting, like the ones returned by `RecordingI2c`
+    fn block_on<F: Future>(future: F) -> F::Output {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+
+        match pin!(future).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("Future should complete immediately"),
+        }
+    }
+
+    #[test]
+    fn should_set_dac() {
+        let mut dac = AsyncMCP4725::new(RecordingI2c::new(), 0b010);
+
+        block_on(dac.set_dac(PowerDown::Normal, 0x0fff)).unwrap();
+
+        assert_eq!(dac.destroy().writes(), &[(0x62, vec![0x40, 0xff, 0xf0])]);
+    }
+
+    #[test]
+    fn should_set_dac_and_eeprom() {
+        let mut dac = AsyncMCP4725::new(RecordingI2c::new(), 0b010);
+
+        block_on(dac.set_dac_and_eeprom(PowerDown::Resistor100kOhm, 0x0fff)).unwrap();
+
+        assert_eq!(dac.destroy().writes(), &[(0x62, vec![0x64, 0xff, 0xf0])]);
+    }
+
+    #[test]
+    fn should_set_dac_fast() {
+        let mut dac = AsyncMCP4725::new(RecordingI2c::new(), 0b010);
+
+        block_on(dac.set_dac_fast(PowerDown::Normal, 0x0877)).unwrap();
+
+        assert_eq!(dac.destroy().writes(), &[(0x62, vec![0x08, 0x77])]);
+    }
+
+    #[test]
+    fn should_read_status() {
+        let mut i2c = RecordingI2c::new();
+        i2c.push_read(&[0xc0, 0xff, 0xf0, 0x08, 0x00]);
+        let mut dac = AsyncMCP4725::new(i2c, 0b010);
+
+        let status = block_on(dac.read()).unwrap();
+
+        assert_eq!(status.data(), 0x0fff);
+        assert_eq!(status.eeprom_data(), 0x0800);
+    }
+}
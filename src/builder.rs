@@ -0,0 +1,126 @@
+//! Builder for configuring an MCP4725 driver
+
+use crate::encode::encode_address;
+use crate::{PowerDown, MCP4725};
+use embedded_hal::i2c::I2c;
+
+/// Builder for an MCP4725 driver. Use this instead of `MCP4725::new` to set configuration options
+/// other than the address;
+/// ```
+/// # use embedded_hal_mock::eh1::i2c::Mock;
+/// # use mcp4725::*;
+/// # let mut i2c = Mock::new(&[]);
+/// let mut dac = MCP4725Builder::new(i2c)
+///     .address(0b010)
+///     .vref_mv(3300)
+///     .default_power(PowerDown::Normal)
+///     .build();
+/// # dac.destroy().done();
+/// ```
+#[derive(Debug)]
+pub struct MCP4725Builder<I2C>
+where
+    I2C: I2c,
+{
+    i2c: I2C,
+    user_address: u8,
+    vref_mv: Option<u16>,
+    default_power: PowerDown,
+}
+
+impl<I2C> MCP4725Builder<I2C>
+where
+    I2C: I2c,
+{
+    /// Start building an MCP4725 driver using the initialized i2c driver port. Defaults to user
+    /// address 0b000, no reference voltage and normal power mode.
+    pub fn new(i2c: I2C) -> Self {
+        MCP4725Builder {
+            i2c,
+            user_address: 0b000,
+            vref_mv: None,
+            default_power: PowerDown::Normal,
+        }
+    }
+
+    /// Set the three bit user-part of the i2c address where the MCP4725 can be reached, see
+    /// `MCP4725::new` for details.
+    pub fn address(self, user_address: u8) -> Self {
+        MCP4725Builder {
+            user_address,
+            ..self
+        }
+    }
+
+    /// Set the reference voltage in millivolts. The MCP4725 uses its supply voltage as reference.
+    /// The driver only stores this value for the application to read back with
+    /// `MCP4725::vref_mv`, for example to pass to `DacStatus::data_voltage_mv`. It is not used by
+    /// any of the driver methods.
+    /// Panics if vref_mv is zero.
+    pub fn vref_mv(self, vref_mv: u16) -> Self {
+        assert!(vref_mv > 0, "Reference voltage should be larger than zero");
+
+        MCP4725Builder {
+            vref_mv: Some(vref_mv),
+            ..self
+        }
+    }
+
+    /// Set a default power down mode for the application to read back with
+    /// `MCP4725::default_power`. The driver only stores this value, all write methods take their
+    /// power down mode as an argument.
+    pub fn default_power(self, default_power: PowerDown) -> Self {
+        MCP4725Builder {
+            default_power,
+            ..self
+        }
+    }
+
+    /// Build the MCP4725 driver
+    pub fn build(self) -> MCP4725<I2C> {
+        MCP4725 {
+            i2c: self.i2c,
+            address: encode_address(self.user_address),
+            vref_mv: self.vref_mv,
+            default_power: self.default_power,
+            last_written: None,
+            eeprom_writing: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use embedded_hal_mock::eh1::i2c::Mock;
+
+    #[test]
+    fn should_build_with_defaults() {
+        let dac = MCP4725Builder::new(Mock::new(&[])).build();
+
+        assert_eq!(dac.address(), 0x60);
+        assert_eq!(dac.vref_mv(), None);
+        assert_eq!(dac.default_power(), PowerDown::Normal);
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_build_with_options() {
+        let dac = MCP4725Builder::new(Mock::new(&[]))
+            .address(0b011)
+            .vref_mv(5000)
+            .default_power(PowerDown::Resistor1kOhm)
+            .build();
+
+        assert_eq!(dac.address(), 0x63);
+        assert_eq!(dac.vref_mv(), Some(5000));
+        assert_eq!(dac.default_power(), PowerDown::Resistor1kOhm);
+        dac.destroy().done();
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_zero_vref() {
+        MCP4725Builder::new(Mock::new(&[])).vref_mv(0);
+    }
+}
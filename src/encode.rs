@@ -1,30 +1,73 @@
 //! Functions for encoding messages to send to the MCP4725
 
+use crate::levels::FULL_SCALE;
 use crate::{CommandType, PowerDown};
 
-/// The device id part of the MCP4725 address
-const DEVICE_ID: u8 = 0b1100000;
+/// The device id part of the MCP4725 address. The three least significant bits are zero, these hold
+/// the user address. Use this when sending raw transactions to the MCP4725.
+pub const DEVICE_ID: u8 = 0b1100000;
 
 /// Use the MCP4725 device id and the three bit user_address to encode the complete DAC address
-pub fn encode_address(user_address: u8) -> u8 {
+pub const fn encode_address(user_address: u8) -> u8 {
     DEVICE_ID | (user_address & 0b00000111)
 }
 
-/// Encode command type, powerdown mode and data into a three byte command
-pub fn encode_command(command: CommandType, power: PowerDown, data: u16) -> [u8; 3] {
+/// Use the MCP4725 device id and the three bit user_address to encode the complete DAC address.
+/// Returns None if user_address has any bits set above the three least significant bits
+pub fn encode_address_checked(user_address: u8) -> Option<u8> {
+    if user_address & !0b00000111 == 0 {
+        Some(encode_address(user_address))
+    } else {
+        None
+    }
+}
+
+/// Limit data to the 12 bit range of the dac. Values above 0x0fff are clamped to 0x0fff instead of
+/// wrapping around.
+pub(crate) const fn clamp_data(data: u16) -> u16 {
+    if data > FULL_SCALE {
+        FULL_SCALE
+    } else {
+        data
+    }
+}
+
+/// Encode command type, powerdown mode and data into a three byte command. Data above 0x0fff is
+/// clamped to 0x0fff.
+/// This is a const fn so commands can be encoded at compile time;
+/// ```
+/// # use mcp4725::*;
+/// const CAL: [u8; 3] = encode_command(CommandType::WriteDac, PowerDown::Normal, 0x0800);
+/// # assert_eq!(CAL, [0x40, 0x80, 0x00]);
+/// ```
+pub const fn encode_command(command: CommandType, power: PowerDown, data: u16) -> [u8; 3] {
+    let data = clamp_data(data);
+
     [
         command as u8 + ((power as u8) << 1),
         (data >> 4) as u8,
-        (data & 0x000f << 4) as u8,
+        (data << 4) as u8,
     ]
 }
 
-/// Encode powerdown mode and data into a two byte fast command
-pub fn encode_fast_command(power: PowerDown, data: u16) -> [u8; 2] {
-    [
-        (power as u8) << 4 | ((data & 0x0fff) >> 8) as u8,
-        data as u8,
-    ]
+/// Encode powerdown mode and data into a two byte fast command. Data above 0x0fff is clamped to
+/// 0x0fff.
+/// This is a const fn so commands can be encoded at compile time;
+/// ```
+/// # use mcp4725::*;
+/// const CAL: [u8; 2] = encode_fast_command(PowerDown::Normal, 0x0800);
+/// # assert_eq!(CAL, [0x08, 0x00]);
+/// ```
+pub const fn encode_fast_command(power: PowerDown, data: u16) -> [u8; 2] {
+    let data = clamp_data(data);
+
+    [(power as u8) << 4 | (data >> 8) as u8, data as u8]
+}
+
+/// Convert a normalized level between 0.0 and 1.0 to a 12 bit dac value, rounding to the nearest
+/// value. Levels outside this range are clamped, NaN is converted to 0.
+pub fn encode_normalized(level: f32) -> u16 {
+    (level.clamp(0.0, 1.0) * FULL_SCALE as f32 + 0.5) as u16
 }
 
 #[cfg(test)]
@@ -42,6 +85,36 @@ mod test {
         assert_eq!(addr, 0b01100010);
     }
 
+    #[test]
+    fn should_encode_all_user_addresses_in_device_range() {
+        for user_address in 0..=0b111u8 {
+            let addr = encode_address(user_address);
+            assert_eq!(addr, 0x60 + user_address);
+            assert_eq!(encode_address_checked(user_address), Some(addr));
+        }
+    }
+
+    #[test]
+    fn should_ignore_exactly_the_bits_above_user_address() {
+        for user_address in 0..=u8::MAX {
+            assert_eq!(encode_address(user_address), 0x60 | (user_address & 0b111));
+            assert_eq!(
+                encode_address_checked(user_address).is_some(),
+                user_address <= 0b111
+            );
+        }
+    }
+
+    #[test]
+    fn should_encode_checked_address_with_device_id() {
+        assert_eq!(encode_address_checked(0b010), Some(0b01100010));
+    }
+
+    #[test]
+    fn should_reject_extra_user_bits_when_checked() {
+        assert_eq!(encode_address_checked(0b00001010), None);
+    }
+
     #[test]
     fn should_encode_command_data() {
         let bytes = encode_command(CommandType::WriteDac, PowerDown::Normal, 0x0fff);
@@ -49,6 +122,13 @@ mod test {
         assert_eq!(bytes, [0b01000000, 0b11111111, 0b11110000])
     }
 
+    #[test]
+    fn should_encode_lowest_command_data_bits() {
+        let bytes = encode_command(CommandType::WriteDac, PowerDown::Normal, 0x0123);
+
+        assert_eq!(bytes, [0b01000000, 0b00010010, 0b00110000])
+    }
+
     #[test]
     fn should_not_encode_command_data_over_12bits() {
         let bytes = encode_command(CommandType::WriteDac, PowerDown::Normal, 0xffff);
@@ -56,6 +136,15 @@ mod test {
         assert_eq!(bytes, [0b01000000, 0b11111111, 0b11110000])
     }
 
+    #[test]
+    fn should_clamp_command_data_over_12bits() {
+        for data in [0x1000, 0x1001, 0xffff].iter() {
+            let bytes = encode_command(CommandType::WriteDac, PowerDown::Normal, *data);
+
+            assert_eq!(bytes, [0b01000000, 0b11111111, 0b11110000])
+        }
+    }
+
     #[test]
     fn should_encode_power_mode() {
         let bytes = encode_command(CommandType::WriteDac, PowerDown::Resistor1kOhm, 0);
@@ -70,6 +159,14 @@ mod test {
         assert_eq!(bytes, [0b01100000, 0, 0])
     }
 
+    #[test]
+    fn should_encode_command_at_compile_time() {
+        const BYTES: [u8; 3] =
+            encode_command(CommandType::WriteDacAndEEPROM, PowerDown::Normal, 0x0fff);
+
+        assert_eq!(BYTES, [0b01100000, 0b11111111, 0b11110000])
+    }
+
     #[test]
     fn should_encode_fastcommand_command_data() {
         let bytes = encode_fast_command(PowerDown::Normal, 0x0877);
@@ -78,10 +175,12 @@ mod test {
     }
 
     #[test]
-    fn should_not_encode_fastcommand_command_data_over_12bits() {
-        let bytes = encode_fast_command(PowerDown::Normal, 0xff77);
+    fn should_clamp_fastcommand_command_data_over_12bits() {
+        for data in [0x1000, 0x1001, 0xff77, 0xffff].iter() {
+            let bytes = encode_fast_command(PowerDown::Normal, *data);
 
-        assert_eq!(bytes, [0b00001111, 0b01110111])
+            assert_eq!(bytes, [0b00001111, 0b11111111])
+        }
     }
 
     #[test]
@@ -90,4 +189,18 @@ mod test {
 
         assert_eq!(bytes, [0b00110000, 0b00000000])
     }
+
+    #[test]
+    fn should_encode_normalized_level() {
+        assert_eq!(encode_normalized(0.0), 0x0000);
+        assert_eq!(encode_normalized(0.5), 0x0800);
+        assert_eq!(encode_normalized(1.0), 0x0fff);
+    }
+
+    #[test]
+    fn should_clamp_normalized_level_out_of_range() {
+        assert_eq!(encode_normalized(-0.5), 0x0000);
+        assert_eq!(encode_normalized(1.5), 0x0fff);
+        assert_eq!(encode_normalized(f32::NAN), 0x0000);
+    }
 }
@@ -0,0 +1,13 @@
+//! Errors returned by the MCP4725 driver
+
+/// MCP4725 driver errors. Wraps errors from the I2C bus and adds errors detected by the driver
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// Error from the underlying I2C bus
+    I2c(E),
+    /// The user address has bits set above the three least significant bits
+    InvalidAddress(u8),
+    /// The MCP4725 has been reset and reloaded its registers from eeprom since the last write
+    UnexpectedReset,
+}
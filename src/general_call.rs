@@ -0,0 +1,54 @@
+//! General call commands for the MCP4725. These are sent to the I2C general call address and are
+//! received by every device on the bus that supports general calls, not just a single MCP4725.
+//! Use these to reset or wake up all MCP4725 chips on a bus at once without constructing a driver
+//! for each one.
+
+use embedded_hal::i2c::I2c;
+
+/// The I2C general call address
+const GENERAL_CALL_ADDRESS: u8 = 0x00;
+
+/// General call reset command byte
+const RESET: u8 = 0x09;
+
+/// General call wake-up command byte
+const WAKE_UP: u8 = 0x06;
+
+/// Send a reset command on the I2C bus.
+/// WARNING: This is a general call command and can reset other devices on the bus as well.
+pub fn reset<I2C: I2c>(i2c: &mut I2C) -> Result<(), I2C::Error> {
+    i2c.write(GENERAL_CALL_ADDRESS, &[RESET])
+}
+
+/// Send a wake-up command over the I2C bus.
+/// WARNING: This is a general call command and can wake-up other devices on the bus as well.
+pub fn wake_up<I2C: I2c>(i2c: &mut I2C) -> Result<(), I2C::Error> {
+    i2c.write(GENERAL_CALL_ADDRESS, &[WAKE_UP])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    extern crate std;
+    use std::vec;
+
+    #[test]
+    fn should_send_general_call_reset() {
+        let mut i2c = Mock::new(&[Transaction::write(0x00, vec![0x09])]);
+
+        reset(&mut i2c).unwrap();
+
+        i2c.done();
+    }
+
+    #[test]
+    fn should_send_general_call_wake_up() {
+        let mut i2c = Mock::new(&[Transaction::write(0x00, vec![0x06])]);
+
+        wake_up(&mut i2c).unwrap();
+
+        i2c.done();
+    }
+}
@@ -0,0 +1,10 @@
+//! Commonly used output levels for the 12 bit dac register
+
+/// Highest output level, the dac output is at the supply voltage
+pub const FULL_SCALE: u16 = 0x0fff;
+
+/// Output level halfway between zero and full scale
+pub const MID_SCALE: u16 = 0x0800;
+
+/// Lowest output level, the dac output is at ground
+pub const ZERO: u16 = 0x0000;
@@ -11,31 +11,90 @@
 //! set in the device. A0 can be set by pulling the corresponding connection on the device high or
 //! low.
 //! ```
-//! # use embedded_hal_mock::i2c::Mock;
+//! # use embedded_hal_mock::eh1::i2c::Mock;
 //! # use mcp4725::*;
 //! # let mut i2c = Mock::new(&[]);
 //! let mut dac = MCP4725::new(i2c, 0b010);
+//! # dac.destroy().done();
+//! ```
+//!
+//! Alternatively the address can be specified using the chip variant and the state of the A0 pin;
+//! ```
+//! # use embedded_hal_mock::eh1::i2c::Mock;
+//! # use mcp4725::*;
+//! # let mut i2c = Mock::new(&[]);
+//! let mut dac = MCP4725::new_with_address(i2c, Address::new(Variant::MCP4725A1, false));
+//! # dac.destroy().done();
+//! ```
+//!
+//! Other configuration options like the reference voltage can be set using the `MCP4725Builder`;
+//! ```
+//! # use embedded_hal_mock::eh1::i2c::Mock;
+//! # use mcp4725::*;
+//! # let mut i2c = Mock::new(&[]);
+//! let mut dac = MCP4725Builder::new(i2c).address(0b010).vref_mv(3300).build();
+//! # dac.destroy().done();
 //! ```
 //!
 //! To set the dac output and powermode the dac register can be set;
 //! ```
-//! # use embedded_hal_mock::i2c::{Mock, Transaction};
+//! # use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
 //! # use mcp4725::*;
 //! # let mut i2c = Mock::new(&[Transaction::write(98, vec![0x40, 0xff, 0xf0]),]);
 //! # let mut dac = MCP4725::new(i2c, 0b010);
 //! dac.set_dac(PowerDown::Normal, 0x0fff);
+//! # dac.destroy().done();
 //! ```
 //!
 //! The MCP4725 has a built in eeprom that is used to initialize the dac register on power up.
 //! The values in the eeprom can be set with the `set_dac_and_eeprom` method;
 //! ```
-//! # use embedded_hal_mock::i2c::{Mock, Transaction};
+//! # use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
 //! # use mcp4725::*;
 //! # let mut i2c = Mock::new(&[Transaction::write(98, vec![0x64, 0xff, 0xf0])]);
 //! # let mut dac = MCP4725::new(i2c, 0b010);
 //! dac.set_dac_and_eeprom(PowerDown::Resistor100kOhm, 0x0fff);
+//! # dac.destroy().done();
+//! ```
+//!
+//! ## Sharing the I2C bus
+//! The driver accepts anything that implements the embedded-hal `I2c` trait. To use multiple
+//! devices on a single bus the driver can be given a bus proxy, for example a `RefCellDevice` from
+//! [embedded-hal-bus](https://crates.io/crates/embedded-hal-bus);
+//! ```
+//! # use core::cell::RefCell;
+//! # use embedded_hal_bus::i2c::RefCellDevice;
+//! # use embedded_hal_mock::eh1::i2c::Mock;
+//! # use mcp4725::*;
+//! # let i2c = Mock::new(&[]);
+//! let bus = RefCell::new(i2c);
+//! let mut dac_0 = MCP4725::new(RefCellDevice::new(&bus), 0b000);
+//! let mut dac_1 = MCP4725::new(RefCellDevice::new(&bus), 0b001);
+//! # drop((dac_0, dac_1));
+//! # bus.into_inner().done();
 //! ```
 //!
+//! A mutable reference to the bus also implements `I2c` so the driver can borrow the bus
+//! temporarily instead;
+//! ```
+//! # use embedded_hal_mock::eh1::i2c::Mock;
+//! # use mcp4725::*;
+//! # let mut i2c = Mock::new(&[]);
+//! let mut dac = MCP4725::new(&mut i2c, 0b010);
+//! # drop(dac);
+//! # i2c.done();
+//! ```
+//!
+//! ## Optional features
+//! - `defmt`: Implement `defmt::Format` for the public types so they can be logged over a defmt
+//!   link.
+//! - `serde`: Implement `Serialize` and `Deserialize` for the public data types.
+//! - `async`: Add the `asynch::AsyncMCP4725` driver built on the embedded-hal-async `I2c` trait.
+//!   Requires Rust 1.75 or later, the rest of the crate supports Rust 1.60.
+//! - `nb`: Add the `nonblocking::NbMCP4725` driver for HALs with `nb` style non-blocking I2C.
+//! - `test-util`: Add the `testing` module with a `RecordingI2c` bus for testing code that uses the
+//!   driver. This depends on `std`.
+//!
 //! ## More information
 //! - [MCP4725 datasheet](http://ww1.microchip.com/downloads/en/DeviceDoc/22039d.pdf)
 //! - [API documentation](https://docs.rs/mcp4725/)
@@ -45,80 +104,524 @@
 #![no_std]
 #![warn(missing_debug_implementations, missing_docs)]
 
+#[cfg(any(test, feature = "test-util"))]
+extern crate std;
+
+mod address;
+#[cfg(feature = "async")]
+pub mod asynch;
+mod builder;
 mod encode;
+mod error;
+pub mod general_call;
+pub mod levels;
+#[cfg(feature = "nb")]
+pub mod nonblocking;
+mod q12;
 mod status;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod waveform;
 
+pub use address::{Address, Variant};
+pub use builder::MCP4725Builder;
 use core::fmt::Debug;
-use embedded_hal::blocking::i2c::{Read, Write};
-use encode::{encode_address, encode_command, encode_fast_command};
-pub use status::DacStatus;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ErrorKind, I2c};
+use encode::{clamp_data, encode_address_checked, encode_normalized};
+pub use encode::{encode_command, encode_fast_command, DEVICE_ID};
+pub use error::Error;
+pub use q12::Q12;
+pub use status::{DacRegister, DacStatus, EepromContents, EepromState};
+
+/// Maximum number of samples sent in a single I2C transaction by `write_fast_slice`
+const FAST_SLICE_CHUNK: usize = 16;
+
+/// Output settling time in microseconds, the typical 6us from the datasheet rounded up
+const SETTLING_TIME_US: u32 = 10;
+
+/// Allowance in microseconds for the output amplifier to power up when leaving a power down mode
+const WAKE_UP_TIME_US: u32 = 10;
+
+/// Load capacitance in picofarads assumed when waiting for the output to settle, this is the load
+/// used for the datasheet specifications
+const LOAD_CAPACITANCE_PF: u32 = 100;
 
 /// MCP4725 DAC driver. Wraps an I2C port to send commands to an MCP4725
 #[derive(Debug)]
 pub struct MCP4725<I2C>
 where
-    I2C: Read + Write,
+    I2C: I2c,
 {
     i2c: I2C,
     address: u8,
+    vref_mv: Option<u16>,
+    default_power: PowerDown,
+    last_written: Option<(PowerDown, u16)>,
+    eeprom_writing: bool,
 }
 
 impl<I2C, E> MCP4725<I2C>
 where
-    I2C: Read<Error = E> + Write<Error = E>,
+    I2C: I2c<Error = E>,
 {
     /// Construct a new MCP4725 driver instance.
     /// i2c is the initialized i2c driver port to use,
     /// user_address is the three bit user-part of the i2c address where the MCP4725 can be reached
     ///   - The least significant bit of this address can be set externally by pulling the A0 leg of
     ///     the chip low (0) or high (1)
-    ///   The two most significant bits are set in the factory. There are four variants of the chip
-    ///     with different addresses.
+    ///   - The two most significant bits are set in the factory. There are four variants of the
+    ///     chip with different addresses.
+    ///
+    /// Any bits set above the three least significant bits of user_address are ignored, use
+    /// `try_new` to catch these. Use `MCP4725Builder` to set other configuration options.
     pub fn new(i2c: I2C, user_address: u8) -> Self {
-        MCP4725 {
-            i2c,
-            address: encode_address(user_address),
-        }
+        MCP4725Builder::new(i2c).address(user_address).build()
     }
 
-    /// Set the dac register
+    /// Construct a new MCP4725 driver instance using the default user address 0b000. This is the
+    /// address of an MCP4725A0, the factory default variant, with the A0 pin pulled low.
+    pub fn new_default(i2c: I2C) -> Self {
+        Self::new(i2c, 0b000)
+    }
+
+    /// Construct a new MCP4725 driver instance, checking the user address.
+    /// i2c is the initialized i2c driver port to use,
+    /// user_address is the three bit user-part of the i2c address where the MCP4725 can be reached
+    ///   - The least significant bit of this address can be set externally by pulling the A0 leg of
+    ///     the chip low (0) or high (1)
+    ///   - The two most significant bits are set in the factory. There are four variants of the
+    ///     chip with different addresses.
+    ///
+    /// Returns `Error::InvalidAddress` if any bits are set above the three least significant bits
+    /// of user_address.
+    pub fn try_new(i2c: I2C, user_address: u8) -> Result<Self, Error<E>> {
+        encode_address_checked(user_address).ok_or(Error::InvalidAddress(user_address))?;
+
+        Ok(Self::new(i2c, user_address))
+    }
+
+    /// Construct a new MCP4725 driver instance using a type-safe address.
+    /// i2c is the initialized i2c driver port to use,
+    /// address is the chip variant and the state of the A0 pin of the MCP4725 to talk to
+    pub fn new_with_address(i2c: I2C, address: Address) -> Self {
+        Self::new(i2c, address.user_address())
+    }
+
+    /// Construct a new MCP4725 driver instance for an MCP4725A0 variant chip.
+    /// a0_pin is true if the A0 pin of the chip is pulled high, false if it is pulled low
+    pub fn new_a0(i2c: I2C, a0_pin: bool) -> Self {
+        Self::new_with_address(i2c, Address::new(Variant::MCP4725A0, a0_pin))
+    }
+
+    /// Construct a new MCP4725 driver instance for an MCP4725A1 variant chip.
+    /// a0_pin is true if the A0 pin of the chip is pulled high, false if it is pulled low
+    pub fn new_a1(i2c: I2C, a0_pin: bool) -> Self {
+        Self::new_with_address(i2c, Address::new(Variant::MCP4725A1, a0_pin))
+    }
+
+    /// Construct a new MCP4725 driver instance for an MCP4725A2 variant chip.
+    /// a0_pin is true if the A0 pin of the chip is pulled high, false if it is pulled low
+    pub fn new_a2(i2c: I2C, a0_pin: bool) -> Self {
+        Self::new_with_address(i2c, Address::new(Variant::MCP4725A2, a0_pin))
+    }
+
+    /// Construct a new MCP4725 driver instance for an MCP4725A3 variant chip.
+    /// a0_pin is true if the A0 pin of the chip is pulled high, false if it is pulled low
+    pub fn new_a3(i2c: I2C, a0_pin: bool) -> Self {
+        Self::new_with_address(i2c, Address::new(Variant::MCP4725A3, a0_pin))
+    }
+
+    /// Set the dac register. Data above 0x0fff is clamped to 0x0fff.
     pub fn set_dac(&mut self, power: PowerDown, data: u16) -> Result<(), E> {
+        self.set_dac_reporting(power, data).map(|_| ())
+    }
+
+    /// Set the dac register and return the three command bytes that were sent
+    pub fn set_dac_reporting(&mut self, power: PowerDown, data: u16) -> Result<[u8; 3], E> {
         let bytes = encode_command(CommandType::WriteDac, power, data);
-        self.i2c.write(self.address, &bytes)
+        self.last_written = None;
+        self.i2c.write(self.address, &bytes)?;
+        self.remember_written(Some((power, clamp_data(data))));
+
+        Ok(bytes)
+    }
+
+    /// Set the dac register and read it back to verify the write. Returns true if the power down
+    /// mode and data read back match what was written. Data above 0x0fff is clamped to 0x0fff
+    /// before it is written so it is compared to the clamped value.
+    pub fn set_dac_verified(&mut self, power: PowerDown, data: u16) -> Result<bool, E> {
+        self.set_dac(power, data)?;
+        let status = self.read()?;
+
+        Ok(status.power_down() == power && status.data() == clamp_data(data))
     }
 
-    /// Set the dac and eeprom registers
+    /// Set the dac register unless the same power down mode and data were the last values the
+    /// driver successfully wrote to the dac register. Returns true when the command was sent and
+    /// false when the write was skipped. Saves bus traffic in control loops that set the dac on
+    /// every tick. The last written values are forgotten after a failed write, `write_raw`,
+    /// `reset` and `wake_up`. After writing the eeprom they are not remembered until a read, for
+    /// example by `wait_eeprom_write`, shows the eeprom write has completed.
+    pub fn set_dac_if_changed(&mut self, power: PowerDown, data: u16) -> Result<bool, E> {
+        if self.last_written == Some((power, clamp_data(data))) {
+            return Ok(false);
+        }
+
+        self.set_dac(power, data)?;
+        Ok(true)
+    }
+
+    /// Set the dac register only if the MCP4725 has not been reset since the last write through
+    /// this driver. The status is read first and if the dac register no longer holds the last
+    /// written values but holds the values stored in eeprom instead, the chip has reloaded its
+    /// registers after a reset or brown-out. `Error::UnexpectedReset` is returned without writing,
+    /// so the application can re-initialize the chip instead of writing on top of the values
+    /// reloaded from eeprom. When nothing was written through the driver yet, or the last written
+    /// values were forgotten, a reset can not be detected and the write always goes ahead.
+    /// A reset can also not be detected when the last written values are the same as the values
+    /// stored in eeprom, for example after `set_dac_and_eeprom`, because the values reloaded after
+    /// the reset are the values that were already in the dac register.
+    /// Values written while an eeprom write may still be in progress are not remembered because
+    /// the MCP4725 ignores them. Call `wait_eeprom_write` after writing the eeprom to detect resets
+    /// again from the next write.
+    /// This costs one read and one write on the I2C bus.
+    pub fn set_dac_guarded(&mut self, power: PowerDown, data: u16) -> Result<(), Error<E>> {
+        let status = self.read().map_err(Error::I2c)?;
+
+        if let Some(last_written) = self.last_written {
+            if status.matches_eeprom() && (status.power_down(), status.data()) != last_written {
+                return Err(Error::UnexpectedReset);
+            }
+        }
+
+        self.set_dac(power, data).map_err(Error::I2c)
+    }
+
+    /// Set the dac and eeprom registers. Data above 0x0fff is clamped to 0x0fff.
     pub fn set_dac_and_eeprom(&mut self, power: PowerDown, data: u16) -> Result<(), E> {
+        self.set_dac_and_eeprom_reporting(power, data).map(|_| ())
+    }
+
+    /// Set the dac and eeprom registers and return the three command bytes that were sent
+    pub fn set_dac_and_eeprom_reporting(
+        &mut self,
+        power: PowerDown,
+        data: u16,
+    ) -> Result<[u8; 3], E> {
         let bytes = encode_command(CommandType::WriteDacAndEEPROM, power, data);
-        self.i2c.write(self.address, &bytes)
+        self.last_written = None;
+        self.i2c.write(self.address, &bytes)?;
+        self.eeprom_writing = true;
+
+        Ok(bytes)
     }
 
-    /// Use the two byte fast command to set the dac register
+    /// Use the two byte fast command to set the dac register. Data above 0x0fff is clamped to
+    /// 0x0fff.
     pub fn set_dac_fast(&mut self, power: PowerDown, data: u16) -> Result<(), E> {
+        self.set_dac_fast_reporting(power, data).map(|_| ())
+    }
+
+    /// Use the two byte fast command to set the dac register and return the two command bytes that
+    /// were sent
+    pub fn set_dac_fast_reporting(&mut self, power: PowerDown, data: u16) -> Result<[u8; 2], E> {
         let bytes = encode_fast_command(power, data);
-        self.i2c.write(self.address, &bytes)
+        self.last_written = None;
+        self.i2c.write(self.address, &bytes)?;
+        self.remember_written(Some((power, clamp_data(data))));
+
+        Ok(bytes)
+    }
+
+    /// Use fast commands to set the dac register to each value in data in turn. The MCP4725 accepts
+    /// multiple fast commands in a single I2C transaction so the values are sent in transactions of
+    /// up to 16 samples instead of one transaction per sample. Each sample takes two bytes on the
+    /// bus in the same layout as `set_dac_fast`; the power down bits and the upper four data bits
+    /// followed by the lower eight data bits.
+    pub fn write_fast_slice(&mut self, power: PowerDown, data: &[u16]) -> Result<(), E> {
+        let mut buffer = [0u8; 2 * FAST_SLICE_CHUNK];
+        self.last_written = None;
+
+        for chunk in data.chunks(FAST_SLICE_CHUNK) {
+            for (frame, value) in buffer.chunks_exact_mut(2).zip(chunk) {
+                frame.copy_from_slice(&encode_fast_command(power, *value));
+            }
+
+            self.i2c.write(self.address, &buffer[..2 * chunk.len()])?;
+        }
+
+        self.remember_written(data.last().map(|value| (power, clamp_data(*value))));
+        Ok(())
+    }
+
+    /// Play samples from an iterator, sending a fast command for each sample in turn. Accepts
+    /// anything that iterates over 12 bit values, like a slice, a `waveform::Ramp` or a computed
+    /// generator. Returns the number of samples sent. Stops at the first I2C error and returns it
+    /// together with the number of samples that were sent before the error.
+    pub fn play<I>(&mut self, power: PowerDown, samples: I) -> Result<usize, (usize, E)>
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        let mut sent = 0;
+
+        for sample in samples {
+            self.set_dac_fast(power, sample)
+                .map_err(|error| (sent, error))?;
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+
+    /// Set the dac register using a 16 bit value. The value is scaled down to the 12 bit range of
+    /// the dac by dropping the four least significant bits, so values are rounded down.
+    pub fn set_dac_16(&mut self, power: PowerDown, value: u16) -> Result<(), E> {
+        self.set_dac(power, value >> 4)
+    }
+
+    /// Set the dac register to a fixed point amplitude. An amplitude of 0.0 sets the output to
+    /// 0x0000 and 1.0 sets it to 0x0fff, amplitudes above 1.0 saturate at 0x0fff.
+    pub fn set_dac_q12(&mut self, power: PowerDown, amplitude: Q12) -> Result<(), E> {
+        self.set_dac(power, amplitude.to_dac())
+    }
+
+    /// Set the dac register to a level expressed as a fraction of full scale. A level of 0.0 sets
+    /// the output to 0x0000 and 1.0 sets it to 0x0fff. Levels outside of this range are clamped.
+    pub fn set_dac_normalized(&mut self, power: PowerDown, level: f32) -> Result<(), E> {
+        self.set_dac(power, encode_normalized(level))
+    }
+
+    /// Write raw bytes to the MCP4725. This is an unchecked escape hatch that bypasses all command
+    /// encoding, the bytes are sent to the chip address as-is.
+    pub fn write_raw(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.last_written = None;
+        self.i2c.write(self.address, bytes)
+    }
+
+    /// Send a command to the MCP4725
+    pub fn send(&mut self, command: &Command) -> Result<(), E> {
+        let bytes = command.to_bytes();
+        self.last_written = None;
+        self.i2c.write(self.address, &bytes)?;
+
+        match command.command_type() {
+            CommandType::WriteDac => {
+                self.remember_written(Some((command.power_mode(), command.data())))
+            }
+            CommandType::WriteDacAndEEPROM => self.eeprom_writing = true,
+        }
+
+        Ok(())
+    }
+
+    /// Remember the values written to the dac register for `set_dac_if_changed` and
+    /// `set_dac_guarded`. The MCP4725 ignores writes while an eeprom write is in progress, so
+    /// nothing is remembered until a read shows the last eeprom write has completed.
+    fn remember_written(&mut self, written: Option<(PowerDown, u16)>) {
+        self.last_written = if self.eeprom_writing { None } else { written };
     }
 
     /// Send read command and return the dac status
     pub fn read(&mut self) -> Result<DacStatus, E> {
         let mut buffer: [u8; 5] = [0; 5];
-        self.i2c.read(self.address, &mut buffer)?;
+        self.read_into(&mut buffer)
+    }
+
+    /// Send read command into a caller provided buffer and return the dac status. The raw bytes
+    /// read are left in the buffer so it can be re-used across reads.
+    pub fn read_into(&mut self, buffer: &mut [u8; 5]) -> Result<DacStatus, E> {
+        self.i2c.read(self.address, buffer)?;
+        let status = DacStatus::from(*buffer);
+
+        if status.eeprom_state() == EepromState::Completed {
+            self.eeprom_writing = false;
+        }
+
+        Ok(status)
+    }
+
+    /// Check if an MCP4725 is present at the configured address by reading its status. Returns
+    /// false if the read is not acknowledged. Other bus errors are returned as errors.
+    /// Some HALs do not report a missing acknowledge separately and return a generic error instead,
+    /// in that case an absent chip results in an error instead of false.
+    pub fn is_present(&mut self) -> Result<bool, E>
+    where
+        E: embedded_hal::i2c::Error,
+    {
+        match self.read() {
+            Ok(_) => Ok(true),
+            Err(error) => match error.kind() {
+                ErrorKind::NoAcknowledge(_) => Ok(false),
+                _ => Err(error),
+            },
+        }
+    }
+
+    /// Send read command and return the data currently stored in the dac register
+    pub fn read_dac(&mut self) -> Result<u16, E> {
+        Ok(self.read()?.data())
+    }
+
+    /// Send read command and return the data stored in eeprom
+    pub fn read_eeprom(&mut self) -> Result<u16, E> {
+        Ok(self.read()?.eeprom_data())
+    }
+
+    /// Wait for an eeprom write started by `set_dac_and_eeprom` to complete. The status is read up
+    /// to max_polls times with a delay of poll_ms milliseconds between reads.
+    /// Returns true when the eeprom write has completed or false when it is still in progress after
+    /// the last read.
+    pub fn wait_eeprom_write<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        poll_ms: u32,
+        max_polls: u16,
+    ) -> Result<bool, E> {
+        self.poll_status(delay, poll_ms, max_polls, DacStatus::eeprom_write_status)
+    }
+
+    /// Read the status up to max_polls times with a delay of poll_ms milliseconds between reads
+    /// until ready returns true for the status. Returns false if it never did.
+    fn poll_status<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        poll_ms: u32,
+        max_polls: u16,
+        ready: fn(&DacStatus) -> bool,
+    ) -> Result<bool, E> {
+        for poll in 0..max_polls {
+            if poll > 0 {
+                delay.delay_ms(poll_ms);
+            }
+
+            if ready(&self.read()?) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
 
-        Ok(buffer.into())
+    /// Change the power down mode while keeping the current value of the dac register.
+    /// This reads the dac register first so it costs one read and one write on the I2C bus.
+    pub fn set_power_down(&mut self, power: PowerDown) -> Result<(), E> {
+        let status = self.read()?;
+        self.set_dac(power, status.data())
+    }
+
+    /// Wait for the output to settle after setting the dac register, previous is the power down
+    /// mode the output was in before.
+    /// This assumes the 100pF load the datasheet specifications use. When the output was driven
+    /// it waits for the settling time. When leaving a power down mode it also waits for the output
+    /// amplifier to power up and for five time constants of the pull down resistor and the load.
+    /// Heavier loads need longer delays than this.
+    pub fn wait_settle<D: DelayNs>(&self, delay: &mut D, previous: PowerDown) {
+        let delay_us = match previous.resistance_ohms() {
+            None => SETTLING_TIME_US,
+            Some(ohms) => {
+                // 5 * R * C in microseconds, rounded up
+                let discharge_us = (5 * ohms * LOAD_CAPACITANCE_PF + 999_999) / 1_000_000;
+                SETTLING_TIME_US + WAKE_UP_TIME_US + discharge_us
+            }
+        };
+
+        delay.delay_us(delay_us);
+    }
+
+    /// Change the power down mode stored in eeprom while keeping the data stored in eeprom. The dac
+    /// register is set to the same values.
+    /// This reads the eeprom first so it costs one read and one write on the I2C bus.
+    pub fn set_power_down_and_eeprom(&mut self, power: PowerDown) -> Result<(), E> {
+        let status = self.read()?;
+        self.set_dac_and_eeprom(power, status.eeprom_data())
+    }
+
+    /// Set the dac register to the data and power down mode stored in eeprom. Unlike `reset` this
+    /// only affects this MCP4725.
+    /// This reads the eeprom first so it costs one read and one write on the I2C bus.
+    pub fn restore_from_eeprom(&mut self) -> Result<(), E> {
+        let status = self.read()?;
+        self.set_dac(status.eeprom_power_down(), status.eeprom_data())
     }
 
     /// Send a wake-up command over the I2C bus.
     /// WARNING: This is a general call command and can wake-up other devices on the bus as well.
+    /// See `general_call::wake_up` to send this command without a driver instance.
     pub fn wake_up(&mut self) -> Result<(), E> {
-        self.i2c.write(0x00, &[0x06u8])?;
-        Ok(())
+        self.last_written = None;
+        general_call::wake_up(&mut self.i2c)
     }
 
     /// Send a reset command on the I2C bus.
     /// WARNING: This is a general call command and can reset other devices on the bus as well.
+    /// See `general_call::reset` to send this command without a driver instance.
     pub fn reset(&mut self) -> Result<(), E> {
-        self.i2c.write(0x00, &[0x09u8])?;
-        Ok(())
+        self.last_written = None;
+        general_call::reset(&mut self.i2c)
+    }
+
+    /// Send a reset command on the I2C bus and wait for this MCP4725 to finish reloading its
+    /// registers from eeprom. The status is read up to max_polls times, waiting poll_ms
+    /// milliseconds before each read, until the power on reset flag is set. A read that is not
+    /// acknowledged while the chip is reloading counts as not ready, unless it is the last read.
+    /// Returns true when the MCP4725 is ready or false when the flag is still not set after the
+    /// last read.
+    /// WARNING: This is a general call command and can reset other devices on the bus as well.
+    pub fn reset_and_wait<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        poll_ms: u32,
+        max_polls: u16,
+    ) -> Result<bool, E>
+    where
+        E: embedded_hal::i2c::Error,
+    {
+        self.reset()?;
+
+        for poll in 1..=max_polls {
+            delay.delay_ms(poll_ms);
+
+            match self.read() {
+                Ok(status) if status.por() => return Ok(true),
+                Ok(_) => {}
+                Err(error) => match error.kind() {
+                    ErrorKind::NoAcknowledge(_) if poll < max_polls => {}
+                    _ => return Err(error),
+                },
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// The 7 bit I2C address the driver uses to reach the MCP4725
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// The reference voltage in millivolts as configured with `MCP4725Builder::vref_mv`. This is
+    /// only stored for the application, the driver does not use it.
+    pub fn vref_mv(&self) -> Option<u16> {
+        self.vref_mv
+    }
+
+    /// The default power down mode as configured with `MCP4725Builder::default_power`. This is only
+    /// stored for the application, the driver does not use it.
+    pub fn default_power(&self) -> PowerDown {
+        self.default_power
+    }
+
+    /// Borrow the wrapped I2C
+    pub fn i2c(&self) -> &I2C {
+        &self.i2c
+    }
+
+    /// Mutably borrow the wrapped I2C, for example to talk to other devices on the bus.
+    /// Commands sent to the MCP4725 directly through the I2C bypass the driver, so
+    /// `set_dac_if_changed` does not see them.
+    pub fn i2c_mut(&mut self) -> &mut I2C {
+        &mut self.i2c
     }
 
     /// Destroy the MCP4725 driver, return the wrapped I2C
@@ -129,6 +632,8 @@ where
 
 /// Two bit flags indicating the power down mode for the MCP4725
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PowerDown {
     /// Normal mode
@@ -153,8 +658,34 @@ impl From<u8> for PowerDown {
     }
 }
 
+impl PowerDown {
+    /// The resistance in ohms that pulls the output to ground in this power down mode. Returns
+    /// None in normal mode where the output is driven.
+    pub fn resistance_ohms(&self) -> Option<u32> {
+        match self {
+            PowerDown::Normal => None,
+            PowerDown::Resistor1kOhm => Some(1_000),
+            PowerDown::Resistor100kOhm => Some(100_000),
+            PowerDown::Resistor500kOhm => Some(500_000),
+        }
+    }
+}
+
+impl core::fmt::Display for PowerDown {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        formatter.write_str(match self {
+            PowerDown::Normal => "Normal",
+            PowerDown::Resistor1kOhm => "1kΩ to GND",
+            PowerDown::Resistor100kOhm => "100kΩ to GND",
+            PowerDown::Resistor500kOhm => "500kΩ to GND",
+        })
+    }
+}
+
 /// The type of the command to send for a Command
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum CommandType {
     /// Write the dac register only
@@ -163,19 +694,70 @@ pub enum CommandType {
     WriteDacAndEEPROM = 0x60,
 }
 
-/// A Command to send to the MCP4725.
-/// Using the address(), command_type(), power_mode() and data() builder methods the
-/// parameters for this command can be set. Commands can be sent using the send method on the
-/// MCP4725 driver.
-/// A command can (and should) be re-used. data() can be used to re-set the data while keeping other
-/// parameters the same.
-#[derive(Debug, Eq, PartialEq)]
+/// A Command to send to the MCP4725 with `MCP4725::send`.
+/// Commands are constructed from a command type, power down mode and data using new(), from the
+/// status read back from a chip, or from captured bytes using from_bytes(). The command_type(),
+/// power_mode() and data() methods decode these parameters from the command.
+///
+/// Converting a status into a command re-applies the dac register, for example to copy the
+/// configuration of one chip to another;
+/// ```
+/// # use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+/// # use mcp4725::*;
+/// # let mut i2c = Mock::new(&[
+/// #     Transaction::read(0x62, vec![0xc2, 0x80, 0x00, 0x0f, 0xff]),
+/// #     Transaction::write(0x63, vec![0x42, 0x80, 0x00]),
+/// # ]);
+/// # let mut dac = MCP4725::new(i2c, 0b010);
+/// let status = dac.read()?;
+///
+/// let mut other = MCP4725::new(dac.destroy(), 0b011);
+/// other.send(&(&status).into())?;
+/// # other.destroy().done();
+/// # Ok::<(), embedded_hal::i2c::ErrorKind>(())
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "CommandFields", into = "CommandFields")
+)]
 pub struct Command {
     command_byte: u8,
     data_byte_0: u8,
     data_byte_1: u8,
 }
 
+/// Decoded fields of a Command, used to serialize the command as its parameters instead of raw
+/// bytes. Deserializing goes through `Command::new` so only valid commands can be constructed.
+#[cfg(feature = "serde")]
+#[derive(Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Command")]
+struct CommandFields {
+    command_type: CommandType,
+    power_down: PowerDown,
+    data: u16,
+}
+
+#[cfg(feature = "serde")]
+impl From<Command> for CommandFields {
+    fn from(command: Command) -> Self {
+        Self {
+            command_type: command.command_type(),
+            power_down: command.power_mode(),
+            data: command.data(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<CommandFields> for Command {
+    fn from(fields: CommandFields) -> Self {
+        Command::new(fields.command_type, fields.power_down, fields.data)
+    }
+}
+
 impl Default for Command {
     /// Instantiate a command with sane defaults.
     fn default() -> Self {
@@ -186,3 +768,911 @@ impl Default for Command {
         }
     }
 }
+
+impl Command {
+    /// Construct a command of command_type that sets power down mode power and data
+    pub fn new(command_type: CommandType, power: PowerDown, data: u16) -> Self {
+        let [command_byte, data_byte_0, data_byte_1] = encode_command(command_type, power, data);
+
+        Self {
+            command_byte,
+            data_byte_0,
+            data_byte_1,
+        }
+    }
+
+    /// Construct a command from the three bytes sent on the I2C bus, for example from a capture of
+    /// the bus traffic. The bytes are stored as-is. Returns None if the first byte is not a write
+    /// dac or write dac and eeprom command, like the first byte of a fast command or one of the
+    /// reserved command codes.
+    pub fn from_bytes(bytes: [u8; 3]) -> Option<Self> {
+        let [command_byte, data_byte_0, data_byte_1] = bytes;
+
+        match command_byte & 0b11100000 {
+            0x40 | 0x60 => Some(Self {
+                command_byte,
+                data_byte_0,
+                data_byte_1,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The three bytes this command sends on the I2C bus
+    pub fn to_bytes(&self) -> [u8; 3] {
+        [self.command_byte, self.data_byte_0, self.data_byte_1]
+    }
+
+    /// The type of this command
+    pub fn command_type(&self) -> CommandType {
+        match self.command_byte & 0b11100000 {
+            0x60 => CommandType::WriteDacAndEEPROM,
+            _ => CommandType::WriteDac,
+        }
+    }
+
+    /// The power down mode this command sets
+    pub fn power_mode(&self) -> PowerDown {
+        // Should never fail. This distills a two bit value from the command byte, PowerMode is
+        // defined for each of the four possible values.
+        ((self.command_byte & 0b00000110) >> 1).into()
+    }
+
+    /// The 12 bit data this command sets
+    pub fn data(&self) -> u16 {
+        (self.data_byte_0 as u16) << 4 | (self.data_byte_1 >> 4) as u16
+    }
+
+    /// Compare the 12 bit data of this command with the data of other, ignoring the command type
+    /// and power down mode. Command does not implement `Ord` because equal commands also need
+    /// equal command types and power down modes.
+    pub fn cmp_data(&self, other: &Command) -> core::cmp::Ordering {
+        self.data().cmp(&other.data())
+    }
+}
+
+impl From<&DacStatus> for Command {
+    /// Construct a command that writes the power down mode and data currently in the dac register
+    /// of the status to the dac register. Use `DacStatus::to_eeprom_command` for the eeprom
+    /// contents.
+    fn from(status: &DacStatus) -> Self {
+        Command::new(CommandType::WriteDac, status.power_down(), status.data())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::waveform::Ramp;
+    use embedded_hal::i2c::NoAcknowledgeSource;
+    use embedded_hal_mock::eh1::delay::{CheckedDelay, NoopDelay, Transaction as DelayTransaction};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    extern crate std;
+    use std::{format, vec};
+
+    #[test]
+    fn should_return_encoded_address() {
+        let dac = MCP4725::new(Mock::new(&[]), 0b011);
+
+        assert_eq!(dac.address(), 0x63);
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_borrow_i2c_for_other_devices() {
+        let i2c = Mock::new(&[Transaction::write(0x10, vec![0x01])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.i2c_mut().write(0x10, &[0x01]).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_write_raw_bytes_to_chip_address() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x01, 0x02, 0x03, 0x04])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.write_raw(&[0x01, 0x02, 0x03, 0x04]).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_read_into_buffer() {
+        let i2c = Mock::new(&[Transaction::read(0x62, vec![0xc0, 0xff, 0xf0, 0x08, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+        let mut buffer = [0u8; 5];
+
+        let status = dac.read_into(&mut buffer).unwrap();
+
+        assert_eq!(buffer, [0xc0, 0xff, 0xf0, 0x08, 0x00]);
+        assert_eq!(status.as_bytes(), &buffer);
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_detect_present_chip() {
+        let i2c = Mock::new(&[Transaction::read(0x62, vec![0xc0, 0, 0, 0, 0])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(dac.is_present().unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_detect_absent_chip_on_nack() {
+        let i2c = Mock::new(&[Transaction::read(0x62, vec![0, 0, 0, 0, 0])
+            .with_error(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(!dac.is_present().unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_return_other_errors_checking_presence() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0, 0, 0, 0, 0]).with_error(ErrorKind::ArbitrationLoss)
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(dac.is_present(), Err(ErrorKind::ArbitrationLoss));
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_read_dac_data() {
+        let i2c = Mock::new(&[Transaction::read(0x62, vec![0xc0, 0xff, 0xf0, 0x08, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(dac.read_dac().unwrap(), 0x0fff);
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_read_eeprom_data() {
+        let i2c = Mock::new(&[Transaction::read(0x62, vec![0xc0, 0xff, 0xf0, 0x08, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(dac.read_eeprom().unwrap(), 0x0800);
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_wait_for_por_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x00, vec![0x09]),
+            Transaction::read(0x62, vec![0x80, 0, 0, 0, 0]),
+            Transaction::read(0x62, vec![0xc0, 0, 0, 0, 0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+        let mut delay =
+            CheckedDelay::new(&[DelayTransaction::delay_ms(1), DelayTransaction::delay_ms(1)]);
+
+        assert!(dac.reset_and_wait(&mut delay, 1, 5).unwrap());
+
+        delay.done();
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_keep_polling_when_not_acknowledged_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x00, vec![0x09]),
+            Transaction::read(0x62, vec![0, 0, 0, 0, 0])
+                .with_error(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+            Transaction::read(0x62, vec![0xc0, 0, 0, 0, 0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(dac.reset_and_wait(&mut NoopDelay::new(), 1, 5).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_return_other_errors_polling_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x00, vec![0x09]),
+            Transaction::read(0x62, vec![0, 0, 0, 0, 0])
+                .with_error(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+            Transaction::read(0x62, vec![0, 0, 0, 0, 0]).with_error(ErrorKind::ArbitrationLoss),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(
+            dac.reset_and_wait(&mut NoopDelay::new(), 1, 5),
+            Err(ErrorKind::ArbitrationLoss)
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_time_out_waiting_for_por_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x00, vec![0x09]),
+            Transaction::read(0x62, vec![0x80, 0, 0, 0, 0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(!dac.reset_and_wait(&mut NoopDelay::new(), 1, 1).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_return_missing_acknowledge_on_last_poll_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x00, vec![0x09]),
+            Transaction::read(0x62, vec![0, 0, 0, 0, 0])
+                .with_error(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(
+            dac.reset_and_wait(&mut NoopDelay::new(), 1, 1),
+            Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_keep_dac_data_when_setting_power_down() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0xc0, 0xff, 0xf0, 0x0f, 0xff]),
+            Transaction::write(0x62, vec![0x44, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_power_down(PowerDown::Resistor100kOhm).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_poll_until_eeprom_write_completes() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0x40, 0, 0, 0, 0]),
+            Transaction::read(0x62, vec![0x40, 0, 0, 0, 0]),
+            Transaction::read(0x62, vec![0xc0, 0, 0, 0, 0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(dac.wait_eeprom_write(&mut NoopDelay::new(), 10, 5).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_time_out_waiting_for_eeprom_write() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0x40, 0, 0, 0, 0]),
+            Transaction::read(0x62, vec![0x40, 0, 0, 0, 0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(!dac.wait_eeprom_write(&mut NoopDelay::new(), 10, 2).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_report_dac_command_bytes() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x40, 0xff, 0xf0])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(
+            dac.set_dac_reporting(PowerDown::Normal, 0x0fff).unwrap(),
+            [0x40, 0xff, 0xf0]
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_report_dac_and_eeprom_command_bytes() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x64, 0xff, 0xf0])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(
+            dac.set_dac_and_eeprom_reporting(PowerDown::Resistor100kOhm, 0x0fff)
+                .unwrap(),
+            [0x64, 0xff, 0xf0]
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_report_fast_command_bytes() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x08, 0x77])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(
+            dac.set_dac_fast_reporting(PowerDown::Normal, 0x0877)
+                .unwrap(),
+            [0x08, 0x77]
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_verify_dac_write() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x42, 0xff, 0xf0]),
+            Transaction::read(0x62, vec![0xc2, 0xff, 0xf0, 0x00, 0x00]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(dac
+            .set_dac_verified(PowerDown::Resistor1kOhm, 0xffff)
+            .unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_fail_verification_when_readback_differs() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::read(0x62, vec![0xc0, 0x00, 0x00, 0x00, 0x00]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(!dac.set_dac_verified(PowerDown::Normal, 0x0fff).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_write_fast_slice_in_one_transaction() {
+        let i2c = Mock::new(&[Transaction::write(
+            0x62,
+            vec![0x00, 0x00, 0x08, 0x00, 0x0f, 0xff],
+        )]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.write_fast_slice(PowerDown::Normal, &[0x0000, 0x0800, 0x0fff])
+            .unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_split_long_fast_slice_into_chunks() {
+        let samples = [0x0123u16; 20];
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, [0x21, 0x23].repeat(16)),
+            Transaction::write(0x62, [0x21, 0x23].repeat(4)),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.write_fast_slice(PowerDown::Resistor100kOhm, &samples)
+            .unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_not_write_empty_fast_slice() {
+        let mut dac = MCP4725::new(Mock::new(&[]), 0b010);
+
+        dac.write_fast_slice(PowerDown::Normal, &[]).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_play_samples_from_iterator() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x00, 0x00]),
+            Transaction::write(0x62, vec![0x08, 0x00]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        let sent = dac
+            .play(PowerDown::Normal, Ramp::new(0x0000, 0x0fff, 0x0800))
+            .unwrap();
+
+        assert_eq!(sent, 2);
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_stop_playing_at_first_error() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x00, 0x00]),
+            Transaction::write(0x62, vec![0x08, 0x00]).with_error(ErrorKind::Other),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        let result = dac.play(PowerDown::Normal, [0x0000, 0x0800, 0x0fff].iter().copied());
+
+        assert_eq!(result, Err((1, ErrorKind::Other)));
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_skip_unchanged_dac_write() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::write(0x62, vec![0x42, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert!(dac.set_dac_if_changed(PowerDown::Normal, 0x0fff).unwrap());
+        assert!(!dac.set_dac_if_changed(PowerDown::Normal, 0xffff).unwrap());
+        assert!(dac
+            .set_dac_if_changed(PowerDown::Resistor1kOhm, 0x0fff)
+            .unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_write_unchanged_dac_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::write(0x00, vec![0x09]),
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        dac.reset().unwrap();
+        assert!(dac.set_dac_if_changed(PowerDown::Normal, 0x0fff).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_write_unchanged_dac_after_failed_write() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::write(0x62, vec![0x0f, 0xff]).with_error(ErrorKind::Other),
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        assert!(dac.set_dac_fast(PowerDown::Normal, 0x0fff).is_err());
+        assert!(dac.set_dac_if_changed(PowerDown::Normal, 0x0fff).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_remember_last_sample_of_fast_slice() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x00, 0x00, 0x08, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.write_fast_slice(PowerDown::Normal, &[0x0000, 0x0800])
+            .unwrap();
+        assert!(!dac.set_dac_if_changed(PowerDown::Normal, 0x0800).unwrap());
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_write_guarded_when_not_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::read(0x62, vec![0xc0, 0xff, 0xf0, 0x08, 0x00]),
+            Transaction::write(0x62, vec![0x40, 0x80, 0x00]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        dac.set_dac_guarded(PowerDown::Normal, 0x0800).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_write_guarded_without_previous_write() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0xc0, 0x80, 0x00, 0x08, 0x00]),
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac_guarded(PowerDown::Normal, 0x0fff).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_not_write_guarded_after_reset() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::read(0x62, vec![0xc0, 0x80, 0x00, 0x08, 0x00]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        assert_eq!(
+            dac.set_dac_guarded(PowerDown::Normal, 0x0fff),
+            Err(Error::UnexpectedReset)
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_not_detect_reset_when_last_write_matches_eeprom() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x40, 0x80, 0x00]),
+            Transaction::read(0x62, vec![0xc0, 0x80, 0x00, 0x08, 0x00]),
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac(PowerDown::Normal, 0x0800).unwrap();
+        // The chip may have been reset, the reloaded values are the same as the last write
+        dac.set_dac_guarded(PowerDown::Normal, 0x0fff).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_not_report_reset_for_write_ignored_during_eeprom_write() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x60, 0x80, 0x00]),
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::read(0x62, vec![0xc0, 0x80, 0x00, 0x08, 0x00]),
+            Transaction::write(0x62, vec![0x40, 0x12, 0x30]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac_and_eeprom(PowerDown::Normal, 0x0800).unwrap();
+        // Ignored by the chip because the eeprom is still being written
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        dac.set_dac_guarded(PowerDown::Normal, 0x0123).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_detect_reset_again_after_eeprom_write_completes() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x62, vec![0x60, 0x80, 0x00]),
+            Transaction::read(0x62, vec![0xc0, 0x80, 0x00, 0x08, 0x00]),
+            Transaction::write(0x62, vec![0x40, 0xff, 0xf0]),
+            Transaction::read(0x62, vec![0xc0, 0x80, 0x00, 0x08, 0x00]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac_and_eeprom(PowerDown::Normal, 0x0800).unwrap();
+        assert!(dac.wait_eeprom_write(&mut NoopDelay::new(), 10, 5).unwrap());
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        assert_eq!(
+            dac.set_dac_guarded(PowerDown::Normal, 0x0123),
+            Err(Error::UnexpectedReset)
+        );
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_scale_16bit_value_to_12bits() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x40, 0x80, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac_16(PowerDown::Normal, 0x800f).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_set_dac_q12() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x40, 0xff, 0xf0])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac_q12(PowerDown::Normal, Q12::ONE).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_set_dac_normalized() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x40, 0xff, 0xf0])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_dac_normalized(PowerDown::Normal, 2.0).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_construct_with_default_address() {
+        let dac = MCP4725::new_default(Mock::new(&[]));
+
+        assert_eq!(dac.address(), 0x60);
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_construct_for_chip_variants() {
+        let dac = MCP4725::new_a0(Mock::new(&[]), false);
+        assert_eq!(dac.address(), 0x60);
+        dac.destroy().done();
+
+        let dac = MCP4725::new_a1(Mock::new(&[]), true);
+        assert_eq!(dac.address(), 0x63);
+        dac.destroy().done();
+
+        let dac = MCP4725::new_a2(Mock::new(&[]), false);
+        assert_eq!(dac.address(), 0x64);
+        dac.destroy().done();
+
+        let dac = MCP4725::new_a3(Mock::new(&[]), true);
+        assert_eq!(dac.address(), 0x67);
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_construct_with_valid_user_address() {
+        let dac = MCP4725::try_new(Mock::new(&[]), 0b111).unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_reject_invalid_user_address() {
+        let mut i2c = Mock::new(&[]);
+        let result = MCP4725::try_new(i2c.clone(), 0b1010);
+
+        assert_eq!(result.err(), Some(Error::InvalidAddress(0b1010)));
+        i2c.done();
+    }
+
+    #[test]
+    fn should_display_power_down() {
+        assert_eq!(format!("{}", PowerDown::Normal), "Normal");
+        assert_eq!(format!("{}", PowerDown::Resistor1kOhm), "1kΩ to GND");
+        assert_eq!(format!("{}", PowerDown::Resistor100kOhm), "100kΩ to GND");
+        assert_eq!(format!("{}", PowerDown::Resistor500kOhm), "500kΩ to GND");
+    }
+
+    #[test]
+    fn should_wait_settling_time_in_normal_mode() {
+        let dac = MCP4725::new(Mock::new(&[]), 0b010);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_us(10)]);
+
+        dac.wait_settle(&mut delay, PowerDown::Normal);
+
+        delay.done();
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_wait_longer_for_weaker_pull_down() {
+        let dac = MCP4725::new(Mock::new(&[]), 0b010);
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_us(21),
+            DelayTransaction::delay_us(70),
+            DelayTransaction::delay_us(270),
+        ]);
+
+        dac.wait_settle(&mut delay, PowerDown::Resistor1kOhm);
+        dac.wait_settle(&mut delay, PowerDown::Resistor100kOhm);
+        dac.wait_settle(&mut delay, PowerDown::Resistor500kOhm);
+
+        delay.done();
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_keep_eeprom_data_when_setting_eeprom_power_down() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0xc0, 0x00, 0x00, 0x0f, 0xff]),
+            Transaction::write(0x62, vec![0x66, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.set_power_down_and_eeprom(PowerDown::Resistor500kOhm)
+            .unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_restore_dac_from_eeprom() {
+        let i2c = Mock::new(&[
+            Transaction::read(0x62, vec![0xc0, 0x00, 0x00, 0x4f, 0xff]),
+            Transaction::write(0x62, vec![0x44, 0xff, 0xf0]),
+        ]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.restore_from_eeprom().unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_return_power_down_resistance() {
+        assert_eq!(PowerDown::Normal.resistance_ohms(), None);
+        assert_eq!(PowerDown::Resistor1kOhm.resistance_ohms(), Some(1_000));
+        assert_eq!(PowerDown::Resistor100kOhm.resistance_ohms(), Some(100_000));
+        assert_eq!(PowerDown::Resistor500kOhm.resistance_ohms(), Some(500_000));
+    }
+
+    #[test]
+    fn should_decode_default_command() {
+        let command = Command::default();
+
+        assert_eq!(command.command_type(), CommandType::WriteDac);
+        assert_eq!(command.power_mode(), PowerDown::Normal);
+        assert_eq!(command.data(), 0x0000);
+    }
+
+    #[test]
+    fn should_decode_command_parameters() {
+        let command = Command::new(
+            CommandType::WriteDacAndEEPROM,
+            PowerDown::Resistor500kOhm,
+            0x0fff,
+        );
+
+        assert_eq!(command.command_type(), CommandType::WriteDacAndEEPROM);
+        assert_eq!(command.power_mode(), PowerDown::Resistor500kOhm);
+        assert_eq!(command.data(), 0x0fff);
+    }
+
+    #[test]
+    fn should_construct_command_from_bytes() {
+        let command = Command::from_bytes([0x64, 0x12, 0x30]).unwrap();
+
+        assert_eq!(command.command_type(), CommandType::WriteDacAndEEPROM);
+        assert_eq!(command.power_mode(), PowerDown::Resistor100kOhm);
+        assert_eq!(command.data(), 0x0123);
+        assert_eq!(command.to_bytes(), [0x64, 0x12, 0x30]);
+    }
+
+    #[test]
+    fn should_reject_bytes_that_are_not_a_command() {
+        // First byte of a fast command
+        assert_eq!(Command::from_bytes([0x08, 0x00, 0x00]), None);
+        // Reserved command codes
+        assert_eq!(Command::from_bytes([0x80, 0x00, 0x00]), None);
+        assert_eq!(Command::from_bytes([0xa0, 0x00, 0x00]), None);
+        assert_eq!(Command::from_bytes([0xe0, 0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn should_round_trip_command_bytes() {
+        for &command_type in &[CommandType::WriteDac, CommandType::WriteDacAndEEPROM] {
+            for &power in &[
+                PowerDown::Normal,
+                PowerDown::Resistor1kOhm,
+                PowerDown::Resistor100kOhm,
+                PowerDown::Resistor500kOhm,
+            ] {
+                for &data in &[0x0000, 0x0001, 0x0123, 0x0800, 0x0fff] {
+                    let command = Command::new(command_type, power, data);
+                    let decoded = Command::from_bytes(command.to_bytes()).unwrap();
+
+                    assert_eq!(decoded, command);
+                    assert_eq!(
+                        decoded.to_bytes(),
+                        encode_command(command_type, power, data)
+                    );
+                    assert_eq!(decoded.command_type(), command_type);
+                    assert_eq!(decoded.power_mode(), power);
+                    assert_eq!(decoded.data(), data);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_serialize_and_deserialize_command_as_fields() {
+        let command = Command::new(
+            CommandType::WriteDacAndEEPROM,
+            PowerDown::Resistor100kOhm,
+            0x0123,
+        );
+
+        let json = serde_json::to_string(&command).unwrap();
+        assert_eq!(
+            json,
+            "{\"command_type\":\"WriteDacAndEEPROM\",\"power_down\":\"Resistor100kOhm\",\
+             \"data\":291}"
+        );
+        assert_eq!(serde_json::from_str::<Command>(&json).unwrap(), command);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_not_deserialize_invalid_command() {
+        // Raw bytes of a fast command
+        assert!(serde_json::from_str::<Command>(
+            "{\"command_byte\":8,\"data_byte_0\":0,\"data_byte_1\":0}"
+        )
+        .is_err());
+        // Reserved command code
+        assert!(serde_json::from_str::<Command>(
+            "{\"command_type\":\"Reserved\",\"power_down\":\"Normal\",\"data\":0}"
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_clamp_deserialized_command_data_over_12bits() {
+        let command: Command = serde_json::from_str(
+            "{\"command_type\":\"WriteDac\",\"power_down\":\"Normal\",\"data\":65535}",
+        )
+        .unwrap();
+
+        assert_eq!(command.to_bytes(), [0x40, 0xff, 0xf0]);
+    }
+
+    #[test]
+    fn should_compare_command_data() {
+        let low = Command::new(CommandType::WriteDacAndEEPROM, PowerDown::Normal, 0x00ff);
+        let high = Command::new(CommandType::WriteDac, PowerDown::Resistor1kOhm, 0x0f00);
+        let same = Command::new(CommandType::WriteDac, PowerDown::Normal, 0x00ff);
+
+        assert_eq!(low.cmp_data(&high), core::cmp::Ordering::Less);
+        assert_eq!(high.cmp_data(&low), core::cmp::Ordering::Greater);
+        assert_eq!(low.cmp_data(&same), core::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn should_convert_status_to_dac_command() {
+        let status: DacStatus = [0xc2, 0x80, 0x00, 0x6f, 0xff].into();
+
+        let command = Command::from(&status);
+
+        assert_eq!(command.command_type(), CommandType::WriteDac);
+        assert_eq!(command.power_mode(), PowerDown::Resistor1kOhm);
+        assert_eq!(command.data(), 0x0800);
+    }
+
+    #[test]
+    fn should_send_command() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x64, 0x80, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.send(&Command::new(
+            CommandType::WriteDacAndEEPROM,
+            PowerDown::Resistor100kOhm,
+            0x0800,
+        ))
+        .unwrap();
+
+        dac.destroy().done();
+    }
+
+    #[test]
+    fn should_remember_sent_dac_command() {
+        let i2c = Mock::new(&[Transaction::write(0x62, vec![0x44, 0x80, 0x00])]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        dac.send(&Command::new(
+            CommandType::WriteDac,
+            PowerDown::Resistor100kOhm,
+            0x0800,
+        ))
+        .unwrap();
+        assert!(!dac
+            .set_dac_if_changed(PowerDown::Resistor100kOhm, 0x0800)
+            .unwrap());
+
+        dac.destroy().done();
+    }
+}
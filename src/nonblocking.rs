@@ -0,0 +1,165 @@
+//! Non-blocking MCP4725 driver for HALs that expose `nb` style I2C writes. Embedded-hal 1.0 has no
+//! non-blocking I2C trait so this module defines a minimal `I2cWrite` trait for HALs or adapters to
+//! implement.
+//!
+//! Writes return `nb::Error::WouldBlock` while the transfer is in progress, the caller keeps
+//! calling the same method with the same arguments until it completes;
+//! ```
+//! # use mcp4725::*;
+//! # use mcp4725::nonblocking::{I2cWrite, NbMCP4725};
+//! # struct I2c;
+//! # impl I2cWrite for I2c {
+//! #     type Error = ();
+//! #     fn write(&mut self, _address: u8, _bytes: &[u8]) -> nb::Result<(), ()> { Ok(()) }
+//! # }
+//! # let i2c = I2c;
+//! let mut dac = NbMCP4725::new(i2c, 0b010);
+//! nb::block!(dac.set_dac_nb(PowerDown::Normal, 0x0fff)).unwrap();
+//! ```
+
+use crate::encode::{encode_address, encode_command, encode_fast_command};
+use crate::{CommandType, PowerDown};
+
+/// Non-blocking I2C write.
+/// The first call starts a transfer, following calls with the same address and bytes return
+/// `nb::Error::WouldBlock` until the transfer has completed.
+pub trait I2cWrite {
+    /// Error type returned by the I2C bus
+    type Error;
+
+    /// Write bytes to the device at address
+    fn write(&mut self, address: u8, bytes: &[u8]) -> nb::Result<(), Self::Error>;
+}
+
+/// Non-blocking MCP4725 DAC driver. Wraps a non-blocking I2C port to send commands to an MCP4725
+#[derive(Debug)]
+pub struct NbMCP4725<I2C>
+where
+    I2C: I2cWrite,
+{
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C, E> NbMCP4725<I2C>
+where
+    I2C: I2cWrite<Error = E>,
+{
+    /// Construct a new non-blocking MCP4725 driver instance.
+    /// i2c is the initialized non-blocking i2c driver port to use,
+    /// user_address is the three bit user-part of the i2c address where the MCP4725 can be reached,
+    /// see `MCP4725::new` for details.
+    pub fn new(i2c: I2C, user_address: u8) -> Self {
+        NbMCP4725 {
+            i2c,
+            address: encode_address(user_address),
+        }
+    }
+
+    /// Set the dac register. Returns `WouldBlock` until the write has completed.
+    pub fn set_dac_nb(&mut self, power: PowerDown, data: u16) -> nb::Result<(), E> {
+        let bytes = encode_command(CommandType::WriteDac, power, data);
+        self.i2c.write(self.address, &bytes)
+    }
+
+    /// Set the dac and eeprom registers. Returns `WouldBlock` until the write has completed.
+    pub fn set_dac_and_eeprom_nb(&mut self, power: PowerDown, data: u16) -> nb::Result<(), E> {
+        let bytes = encode_command(CommandType::WriteDacAndEEPROM, power, data);
+        self.i2c.write(self.address, &bytes)
+    }
+
+    /// Use the two byte fast command to set the dac register. Returns `WouldBlock` until the write
+    /// has completed.
+    pub fn set_dac_fast_nb(&mut self, power: PowerDown, data: u16) -> nb::Result<(), E> {
+        let bytes = encode_fast_command(power, data);
+        self.i2c.write(self.address, &bytes)
+    }
+
+    /// The 7 bit I2C address the driver uses to reach the MCP4725
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Destroy the non-blocking MCP4725 driver, return the wrapped I2C
+    pub fn destroy(self) -> I2C {
+        self.i2c
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    /// I2C that needs a number of polls to finish each write and records the bytes of every call
+    struct SlowI2c {
+        polls_per_write: u8,
+        pending: u8,
+        calls: Vec<(u8, Vec<u8>)>,
+    }
+
+    impl SlowI2c {
+        fn new(polls_per_write: u8) -> Self {
+            SlowI2c {
+                polls_per_write,
+                pending: polls_per_write,
+                calls: Vec::new(),
+            }
+        }
+    }
+
+    impl I2cWrite for SlowI2c {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> nb::Result<(), ()> {
+            self.calls.push((address, bytes.to_vec()));
+
+            if self.pending == 0 {
+                self.pending = self.polls_per_write;
+                Ok(())
+            } else {
+                self.pending -= 1;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    #[test]
+    fn should_block_until_write_completes() {
+        let mut dac = NbMCP4725::new(SlowI2c::new(2), 0b010);
+
+        assert_eq!(
+            dac.set_dac_nb(PowerDown::Normal, 0x0fff),
+            Err(nb::Error::WouldBlock)
+        );
+        assert_eq!(
+            dac.set_dac_nb(PowerDown::Normal, 0x0fff),
+            Err(nb::Error::WouldBlock)
+        );
+        assert_eq!(dac.set_dac_nb(PowerDown::Normal, 0x0fff), Ok(()));
+    }
+
+    #[test]
+    fn should_send_same_bytes_on_every_poll() {
+        let mut dac = NbMCP4725::new(SlowI2c::new(1), 0b010);
+
+        nb::block!(dac.set_dac_and_eeprom_nb(PowerDown::Resistor100kOhm, 0x0fff)).unwrap();
+
+        let calls = dac.destroy().calls;
+        assert_eq!(calls.len(), 2);
+        assert!(calls
+            .iter()
+            .all(|call| call == &(0x62, [0x64, 0xff, 0xf0].to_vec())));
+    }
+
+    #[test]
+    fn should_send_fast_command() {
+        let mut dac = NbMCP4725::new(SlowI2c::new(0), 0b010);
+
+        nb::block!(dac.set_dac_fast_nb(PowerDown::Normal, 0x0877)).unwrap();
+
+        assert_eq!(dac.destroy().calls, [(0x62, [0x08, 0x77].to_vec())]);
+    }
+}
@@ -0,0 +1,61 @@
+//! Fixed point amplitude type for writing to the dac without floating point arithmetic
+
+use crate::levels::FULL_SCALE;
+
+/// Unsigned fixed point amplitude with 12 fractional bits. A value of 0x0000 is 0.0 and 0x1000 is
+/// 1.0, the full scale output of the dac.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Q12(u16);
+
+impl Q12 {
+    /// Amplitude of 0.0
+    pub const ZERO: Q12 = Q12(0x0000);
+
+    /// Amplitude of 1.0
+    pub const ONE: Q12 = Q12(0x1000);
+
+    /// Construct an amplitude from its raw fixed point bits
+    pub const fn from_bits(bits: u16) -> Self {
+        Q12(bits)
+    }
+
+    /// The raw fixed point bits of this amplitude
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    /// Convert to a 12 bit dac value, mapping 0.0..=1.0 to 0..=4095 and rounding to the nearest
+    /// value. Amplitudes above 1.0 saturate at full scale.
+    pub const fn to_dac(self) -> u16 {
+        if self.0 >= Q12::ONE.0 {
+            FULL_SCALE
+        } else {
+            ((self.0 as u32 * FULL_SCALE as u32 + 0x0800) >> 12) as u16
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_map_range_to_dac_values() {
+        assert_eq!(Q12::ZERO.to_dac(), 0x0000);
+        assert_eq!(Q12::from_bits(0x0800).to_dac(), 0x0800);
+        assert_eq!(Q12::ONE.to_dac(), 0x0fff);
+    }
+
+    #[test]
+    fn should_saturate_above_one() {
+        assert_eq!(Q12::from_bits(0x1001).to_dac(), 0x0fff);
+        assert_eq!(Q12::from_bits(0xffff).to_dac(), 0x0fff);
+    }
+
+    #[test]
+    fn should_round_trip_bits() {
+        assert_eq!(Q12::from_bits(0x0123).to_bits(), 0x0123);
+    }
+}
@@ -1,9 +1,17 @@
-use crate::PowerDown;
+#[cfg(feature = "serde")]
+use crate::encode::clamp_data;
+use crate::levels::FULL_SCALE;
+use crate::{Command, CommandType, PowerDown};
 use core::fmt::Debug;
 
 /// The status of the MCP4725 as read by the read command. Contains the DAC register values and the
 /// values stored in EEPROM
 #[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "DacStatusFields", into = "DacStatusFields")
+)]
 pub struct DacStatus {
     bytes: [u8; 5],
 }
@@ -14,10 +22,109 @@ impl From<[u8; 5]> for DacStatus {
     }
 }
 
+/// The DAC register half of a DacStatus
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DacRegister {
+    power_down: PowerDown,
+    data: u16,
+}
+
+impl DacRegister {
+    /// Current power mode setting
+    pub fn power_down(&self) -> PowerDown {
+        self.power_down
+    }
+
+    /// Data currently stored in the DAC register
+    pub fn data(&self) -> u16 {
+        self.data
+    }
+}
+
+/// The EEPROM half of a DacStatus
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EepromContents {
+    power_down: PowerDown,
+    data: u16,
+}
+
+impl EepromContents {
+    /// Power mode stored in eeprom
+    pub fn power_down(&self) -> PowerDown {
+        self.power_down
+    }
+
+    /// Data stored in eeprom
+    pub fn data(&self) -> u16 {
+        self.data
+    }
+}
+
+/// Progress of an eeprom write
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EepromState {
+    /// The eeprom is still being written, new write commands are ignored
+    InProgress,
+    /// The last eeprom write has completed
+    Completed,
+}
+
+/// Decoded fields of a DacStatus, used to serialize the status as its fields instead of raw bytes
+#[cfg(feature = "serde")]
+#[derive(Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename = "DacStatus")]
+struct DacStatusFields {
+    power_down: PowerDown,
+    data: u16,
+    por: bool,
+    eeprom_write_status: bool,
+    eeprom_data: u16,
+    eeprom_power_down: PowerDown,
+}
+
+#[cfg(feature = "serde")]
+impl From<DacStatus> for DacStatusFields {
+    fn from(status: DacStatus) -> Self {
+        Self {
+            power_down: status.power_down(),
+            data: status.data(),
+            por: status.por(),
+            eeprom_write_status: status.eeprom_write_status(),
+            eeprom_data: status.eeprom_data(),
+            eeprom_power_down: status.eeprom_power_down(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<DacStatusFields> for DacStatus {
+    fn from(fields: DacStatusFields) -> Self {
+        let data = clamp_data(fields.data);
+        let eeprom_data = clamp_data(fields.eeprom_data);
+
+        [
+            (fields.eeprom_write_status as u8) << 7
+                | (fields.por as u8) << 6
+                | (fields.power_down as u8) << 1,
+            (data >> 4) as u8,
+            (data << 4) as u8,
+            (fields.eeprom_power_down as u8) << 5 | (eeprom_data >> 8) as u8,
+            eeprom_data as u8,
+        ]
+        .into()
+    }
+}
+
 impl Debug for DacStatus {
     fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         formatter
-            .debug_struct("Point")
+            .debug_struct("DacStatus")
             .field("power_down", &self.power_down())
             .field("data", &self.data())
             .field("por", &self.por())
@@ -28,12 +135,83 @@ impl Debug for DacStatus {
     }
 }
 
+impl core::fmt::Display for DacStatus {
+    /// One line summary of the status for logging or serial consoles, like
+    /// `DAC=2048 (Normal) EEPROM=2048 (Normal) POR=1 EEWR=done`
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            formatter,
+            "DAC={} ({}) EEPROM={} ({}) POR={} EEWR={}",
+            self.data(),
+            self.power_down(),
+            self.eeprom_data(),
+            self.eeprom_power_down(),
+            self.por() as u8,
+            match self.eeprom_state() {
+                EepromState::Completed => "done",
+                EepromState::InProgress => "busy",
+            }
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for DacStatus {
+    fn format(&self, formatter: defmt::Formatter) {
+        defmt::write!(
+            formatter,
+            "DacStatus {{ power_down: {}, data: {}, por: {}, eeprom_write_status: {}, eeprom_data: {}, eeprom_power_down: {} }}",
+            self.power_down(),
+            self.data(),
+            self.por(),
+            self.eeprom_write_status(),
+            self.eeprom_data(),
+            self.eeprom_power_down()
+        )
+    }
+}
+
 impl DacStatus {
+    /// The raw bytes as read from the MCP4725
+    pub const fn as_bytes(&self) -> &[u8; 5] {
+        &self.bytes
+    }
+
+    /// Convert into the raw bytes as read from the MCP4725
+    pub const fn into_bytes(self) -> [u8; 5] {
+        self.bytes
+    }
+
+    /// The power mode and data in the DAC register
+    pub fn dac_register(&self) -> DacRegister {
+        DacRegister {
+            power_down: self.power_down(),
+            data: self.data(),
+        }
+    }
+
+    /// The power mode and data stored in eeprom
+    pub fn eeprom(&self) -> EepromContents {
+        EepromContents {
+            power_down: self.eeprom_power_down(),
+            data: self.eeprom_data(),
+        }
+    }
+
     /// Eeprom write status. true = completed, false = incomplete
     pub fn eeprom_write_status(&self) -> bool {
         self.bytes[0] & 0x80 == 0x80
     }
 
+    /// Eeprom write progress, the same information as `eeprom_write_status`
+    pub fn eeprom_state(&self) -> EepromState {
+        if self.eeprom_write_status() {
+            EepromState::Completed
+        } else {
+            EepromState::InProgress
+        }
+    }
+
     /// Power on reset state
     pub fn por(&self) -> bool {
         self.bytes[0] & 0x40 == 0x40
@@ -46,11 +224,33 @@ impl DacStatus {
         ((self.bytes[0] & 0b00000110) >> 1).into()
     }
 
+    /// true if the output is currently in one of the power down modes, false in normal mode
+    pub fn is_powered_down(&self) -> bool {
+        self.power_down() != PowerDown::Normal
+    }
+
     /// Data currently stored in the DAC register
     pub fn data(&self) -> u16 {
         (self.bytes[1] as u16 * 0x0100 + self.bytes[2] as u16) >> 4
     }
 
+    /// Data currently stored in the DAC register scaled up to a 16 bit value. The four least
+    /// significant bits are always zero.
+    pub fn data_u16(&self) -> u16 {
+        self.data() << 4
+    }
+
+    /// Data currently stored in the DAC register as a fraction of full scale between 0.0 and 1.0
+    pub fn data_normalized(&self) -> f32 {
+        self.data() as f32 / FULL_SCALE as f32
+    }
+
+    /// Output voltage in millivolts for the data currently stored in the DAC register, given the
+    /// reference (supply) voltage in millivolts
+    pub fn data_voltage_mv(&self, vref_mv: u16) -> u16 {
+        code_to_mv(self.data(), vref_mv)
+    }
+
     /// Power mode stored in eeprom
     pub fn eeprom_power_down(&self) -> PowerDown {
         // Should never fail. This distills a two bit value from bytes, PowerMode is defined
@@ -58,17 +258,53 @@ impl DacStatus {
         ((self.bytes[3] & 0b01100000) >> 5).into()
     }
 
+    /// true if the power mode stored in eeprom is one of the power down modes, false in normal mode
+    pub fn eeprom_is_powered_down(&self) -> bool {
+        self.eeprom_power_down() != PowerDown::Normal
+    }
+
     /// Data stored in eeprom
     pub fn eeprom_data(&self) -> u16 {
         (self.bytes[3] & 0x0f) as u16 * 0x0100 + self.bytes[4] as u16
     }
+
+    /// Output voltage in millivolts for the data stored in eeprom, given the reference (supply)
+    /// voltage in millivolts
+    pub fn eeprom_data_voltage_mv(&self, vref_mv: u16) -> u16 {
+        code_to_mv(self.eeprom_data(), vref_mv)
+    }
+
+    /// true if the data and power mode in the DAC register match the values stored in eeprom that
+    /// will be restored on the next power up
+    pub fn matches_eeprom(&self) -> bool {
+        self.data() == self.eeprom_data() && self.power_down() == self.eeprom_power_down()
+    }
+
+    /// Construct a command that writes the power down mode and data stored in eeprom to the dac and
+    /// eeprom registers. Use `Command::from` for the dac register.
+    pub fn to_eeprom_command(&self) -> Command {
+        Command::new(
+            CommandType::WriteDacAndEEPROM,
+            self.eeprom_power_down(),
+            self.eeprom_data(),
+        )
+    }
+}
+
+/// Scale a 12 bit dac code to millivolts, computed in u32 to avoid overflow
+fn code_to_mv(code: u16, vref_mv: u16) -> u16 {
+    (code as u32 * vref_mv as u32 / FULL_SCALE as u32) as u16
 }
 
 #[cfg(test)]
 mod test_status {
     use super::*;
 
+    extern crate std;
+    use std::format;
+
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn should_parse_eeprom_write_status() {
         let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
         assert_eq!(status.eeprom_write_status(), false);
@@ -78,6 +314,16 @@ mod test_status {
     }
 
     #[test]
+    fn should_parse_eeprom_state() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert_eq!(status.eeprom_state(), EepromState::InProgress);
+
+        let status: DacStatus = [0x80u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert_eq!(status.eeprom_state(), EepromState::Completed);
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn should_parse_dac_por() {
         let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
         assert_eq!(status.por(), false);
@@ -95,6 +341,42 @@ mod test_status {
         assert_eq!(status.data(), 0x0fff);
     }
 
+    #[test]
+    fn should_parse_dac_data_as_16bits() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert_eq!(status.data_u16(), 0x0000);
+
+        let status: DacStatus = [0u8, 0xffu8, 0xffu8, 0x0f0u8, 0u8].into();
+        assert_eq!(status.data_u16(), 0xfff0);
+    }
+
+    #[test]
+    fn should_parse_dac_data_normalized() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert_eq!(status.data_normalized(), 0.0);
+
+        let status: DacStatus = [0u8, 0xffu8, 0xffu8, 0x0f0u8, 0u8].into();
+        assert_eq!(status.data_normalized(), 1.0);
+    }
+
+    #[test]
+    fn should_convert_dac_data_to_voltage() {
+        let status: DacStatus = [0u8, 0xffu8, 0xf0u8, 0u8, 0u8].into();
+        assert_eq!(status.data_voltage_mv(3300), 3300);
+
+        let status: DacStatus = [0u8, 0x80u8, 0x00u8, 0u8, 0u8].into();
+        assert_eq!(status.data_voltage_mv(5000), 2500);
+    }
+
+    #[test]
+    fn should_convert_eeprom_data_to_voltage() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0x0fu8, 0xffu8].into();
+        assert_eq!(status.eeprom_data_voltage_mv(u16::MAX), u16::MAX);
+
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert_eq!(status.eeprom_data_voltage_mv(3300), 0);
+    }
+
     #[test]
     fn should_parse_eeprom_data() {
         let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
@@ -113,6 +395,24 @@ mod test_status {
         assert_eq!(status.power_down(), PowerDown::Resistor100kOhm);
     }
 
+    #[test]
+    fn should_detect_dac_powered_down() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert!(!status.is_powered_down());
+
+        let status: DacStatus = [0b00000010u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert!(status.is_powered_down());
+    }
+
+    #[test]
+    fn should_detect_eeprom_powered_down() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert!(!status.eeprom_is_powered_down());
+
+        let status: DacStatus = [0u8, 0u8, 0u8, 0b01000000u8, 0u8].into();
+        assert!(status.eeprom_is_powered_down());
+    }
+
     #[test]
     fn should_parse_eeprom_power() {
         let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
@@ -121,4 +421,139 @@ mod test_status {
         let status: DacStatus = [0u8, 0u8, 0u8, 0xffu8, 0xffu8].into();
         assert_eq!(status.eeprom_power_down(), PowerDown::Resistor500kOhm);
     }
+
+    #[test]
+    fn should_split_off_dac_register() {
+        let status: DacStatus = [0b00000010u8, 0x12u8, 0x30u8, 0b01001111u8, 0xffu8].into();
+        let register = status.dac_register();
+
+        assert_eq!(register.power_down(), PowerDown::Resistor1kOhm);
+        assert_eq!(register.data(), 0x0123);
+    }
+
+    #[test]
+    fn should_split_off_eeprom() {
+        let status: DacStatus = [0b00000010u8, 0x12u8, 0x30u8, 0b01001111u8, 0xffu8].into();
+        let eeprom = status.eeprom();
+
+        assert_eq!(eeprom.power_down(), PowerDown::Resistor100kOhm);
+        assert_eq!(eeprom.data(), 0x0fff);
+    }
+
+    #[test]
+    fn should_match_eeprom() {
+        let status: DacStatus = [0b00000010u8, 0x12u8, 0x30u8, 0b00100001u8, 0x23u8].into();
+        assert!(status.matches_eeprom());
+    }
+
+    #[test]
+    fn should_not_match_eeprom_with_different_data() {
+        let status: DacStatus = [0u8, 0x12u8, 0x30u8, 0x01u8, 0x24u8].into();
+        assert!(!status.matches_eeprom());
+    }
+
+    #[test]
+    fn should_not_match_eeprom_with_different_power_down() {
+        let status: DacStatus = [0b00000010u8, 0x12u8, 0x30u8, 0x01u8, 0x23u8].into();
+        assert!(!status.matches_eeprom());
+    }
+
+    #[test]
+    fn should_convert_eeprom_to_command() {
+        let status: DacStatus = [0xc2u8, 0x80u8, 0x00u8, 0x6fu8, 0xffu8].into();
+
+        let command = status.to_eeprom_command();
+
+        assert_eq!(command.command_type(), CommandType::WriteDacAndEEPROM);
+        assert_eq!(command.power_mode(), PowerDown::Resistor500kOhm);
+        assert_eq!(command.data(), 0x0fff);
+    }
+
+    #[test]
+    fn should_return_raw_bytes() {
+        let status: DacStatus = [0xc0u8, 0x12u8, 0x30u8, 0x01u8, 0x23u8].into();
+        assert_eq!(status.as_bytes(), &[0xc0u8, 0x12u8, 0x30u8, 0x01u8, 0x23u8]);
+        assert_eq!(
+            status.into_bytes(),
+            [0xc0u8, 0x12u8, 0x30u8, 0x01u8, 0x23u8]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_convert_to_and_from_serde_fields() {
+        let status: DacStatus = [0xc4u8, 0x12u8, 0x30u8, 0x41u8, 0x23u8].into();
+        let fields: DacStatusFields = status.into();
+
+        assert_eq!(
+            fields,
+            DacStatusFields {
+                power_down: PowerDown::Resistor100kOhm,
+                data: 0x0123,
+                por: true,
+                eeprom_write_status: true,
+                eeprom_data: 0x0123,
+                eeprom_power_down: PowerDown::Resistor100kOhm,
+            }
+        );
+        assert_eq!(DacStatus::from(fields), status);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_clamp_serde_fields_data_over_12bits() {
+        let fields = DacStatusFields {
+            power_down: PowerDown::Normal,
+            data: 0x1234,
+            por: true,
+            eeprom_write_status: true,
+            eeprom_data: 0xffff,
+            eeprom_power_down: PowerDown::Normal,
+        };
+
+        let status = DacStatus::from(fields);
+
+        assert_eq!(status.data(), 0x0fff);
+        assert_eq!(status.eeprom_data(), 0x0fff);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_serialize_and_deserialize_as_fields() {
+        let status: DacStatus = [0xc4u8, 0x12u8, 0x30u8, 0x41u8, 0x23u8].into();
+
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(
+            json,
+            "{\"power_down\":\"Resistor100kOhm\",\"data\":291,\"por\":true,\
+             \"eeprom_write_status\":true,\"eeprom_data\":291,\
+             \"eeprom_power_down\":\"Resistor100kOhm\"}"
+        );
+        assert_eq!(serde_json::from_str::<DacStatus>(&json).unwrap(), status);
+    }
+
+    #[test]
+    fn should_debug_format_as_dac_status() {
+        let status: DacStatus = [0u8, 0u8, 0u8, 0u8, 0u8].into();
+        assert_eq!(
+            format!("{:?}", status),
+            "DacStatus { power_down: Normal, data: 0, por: false, eeprom_write_status: false, \
+             eeprom_data: 0, eeprom_power_down: Normal }"
+        );
+    }
+
+    #[test]
+    fn should_display_status_summary() {
+        let status: DacStatus = [0xc0u8, 0x80u8, 0x00u8, 0x08u8, 0x00u8].into();
+        assert_eq!(
+            format!("{}", status),
+            "DAC=2048 (Normal) EEPROM=2048 (Normal) POR=1 EEWR=done"
+        );
+
+        let status: DacStatus = [0x04u8, 0xffu8, 0xf0u8, 0x40u8, 0x00u8].into();
+        assert_eq!(
+            format!("{}", status),
+            "DAC=4095 (100kΩ to GND) EEPROM=0 (100kΩ to GND) POR=0 EEWR=busy"
+        );
+    }
 }
@@ -0,0 +1,140 @@
+//! Test utilities for code that uses the MCP4725 driver. Requires the `test-util` feature, which
+//! depends on `std`.
+//!
+//! `RecordingI2c` records every frame written to it and answers reads with preloaded responses. It
+//! also implements the embedded-hal-async `I2c` trait when the `async` feature is enabled;
+//! ```
+//! # use mcp4725::*;
+//! # use mcp4725::testing::RecordingI2c;
+//! let mut i2c = RecordingI2c::new();
+//! i2c.push_read(&[0xc0, 0xff, 0xf0, 0x08, 0x00]);
+//!
+//! let mut dac = MCP4725::new(i2c, 0b010);
+//! dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+//! assert_eq!(dac.read().unwrap().eeprom_data(), 0x0800);
+//!
+//! assert_eq!(dac.i2c().writes(), &[(0x62, vec![0x40, 0xff, 0xf0])]);
+//! ```
+
+use core::convert::Infallible;
+use embedded_hal::i2c::{ErrorType, I2c, Operation};
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+/// I2C bus that records written frames and returns preloaded read responses
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct RecordingI2c {
+    writes: Vec<(u8, Vec<u8>)>,
+    reads: VecDeque<Vec<u8>>,
+}
+
+impl RecordingI2c {
+    /// Construct a recording I2C bus without any recorded writes or preloaded reads
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Preload a response for a read. Responses are returned in the order they were pushed.
+    pub fn push_read(&mut self, bytes: &[u8]) {
+        self.reads.push_back(bytes.to_vec());
+    }
+
+    /// The frames written so far, as pairs of address and bytes
+    pub fn writes(&self) -> &[(u8, Vec<u8>)] {
+        &self.writes
+    }
+
+    /// Return the frames written so far and clear them
+    pub fn take_writes(&mut self) -> Vec<(u8, Vec<u8>)> {
+        core::mem::take(&mut self.writes)
+    }
+}
+
+impl ErrorType for RecordingI2c {
+    type Error = Infallible;
+}
+
+impl I2c for RecordingI2c {
+    /// Record writes and answer reads from the preloaded responses.
+    /// Panics when reading without a preloaded response or when the response has the wrong length.
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.writes.push((address, bytes.to_vec())),
+                Operation::Read(buffer) => {
+                    let response = self
+                        .reads
+                        .pop_front()
+                        .expect("No preloaded response for read");
+                    buffer.copy_from_slice(&response);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for RecordingI2c {
+    /// Record writes and answer reads like the blocking implementation, completes immediately
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PowerDown, MCP4725};
+    use std::vec;
+
+    #[test]
+    fn should_record_written_frames() {
+        let mut dac = MCP4725::new(RecordingI2c::new(), 0b010);
+
+        dac.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        dac.set_dac_fast(PowerDown::Normal, 0x0000).unwrap();
+
+        assert_eq!(
+            dac.destroy().writes(),
+            &[(0x62, vec![0x40, 0xff, 0xf0]), (0x62, vec![0x00, 0x00])]
+        );
+    }
+
+    #[test]
+    fn should_return_preloaded_reads_in_order() {
+        let mut i2c = RecordingI2c::new();
+        i2c.push_read(&[0xc0, 0xff, 0xf0, 0x00, 0x00]);
+        i2c.push_read(&[0xc0, 0x00, 0x00, 0x00, 0x00]);
+        let mut dac = MCP4725::new(i2c, 0b010);
+
+        assert_eq!(dac.read_dac().unwrap(), 0x0fff);
+        assert_eq!(dac.read_dac().unwrap(), 0x0000);
+    }
+
+    #[test]
+    fn should_clear_taken_writes() {
+        let mut i2c = RecordingI2c::new();
+        i2c.write(0x62, &[0x01]).unwrap();
+
+        assert_eq!(i2c.take_writes(), vec![(0x62, vec![0x01])]);
+        assert!(i2c.writes().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_panic_when_reading_without_response() {
+        let mut dac = MCP4725::new(RecordingI2c::new(), 0b010);
+
+        dac.read().unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+//! Generators for simple waveforms that can be sent to the MCP4725
+//!
+//! A ramp can be combined with `set_dac_fast` to create a saw-tooth wave;
+//! ```
+//! # use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+//! # use mcp4725::*;
+//! # use mcp4725::waveform::Ramp;
+//! # let mut i2c = Mock::new(&[
+//! #     Transaction::write(98, vec![0x00, 0x00]),
+//! #     Transaction::write(98, vec![0x08, 0x00]),
+//! # ]);
+//! # let mut dac = MCP4725::new(i2c, 0b010);
+//! for value in Ramp::new(levels::ZERO, levels::FULL_SCALE, levels::MID_SCALE) {
+//!     dac.set_dac_fast(PowerDown::Normal, value)?;
+//! }
+//! # dac.destroy().done();
+//! # Ok::<(), embedded_hal::i2c::ErrorKind>(())
+//! ```
+
+use crate::levels::FULL_SCALE;
+
+/// Iterator that yields 12 bit values increasing from start to end in fixed steps
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Ramp {
+    start: u16,
+    end: u16,
+    step: u16,
+    next: Option<u16>,
+    wrap: bool,
+}
+
+impl Ramp {
+    /// Create a ramp that counts from start up to and including end in increments of step. Start and
+    /// end are limited to the 12 bit range of the dac. If start is larger than end the ramp is
+    /// empty. The ramp stops when the next step would go past end.
+    /// Panics if step is zero.
+    pub fn new(start: u16, end: u16, step: u16) -> Self {
+        assert!(step > 0, "Ramp step should be larger than zero");
+
+        let start = start.min(FULL_SCALE);
+        let end = end.min(FULL_SCALE);
+
+        Ramp {
+            start,
+            end,
+            step,
+            next: if start <= end { Some(start) } else { None },
+            wrap: false,
+        }
+    }
+
+    /// Make the ramp start over at start instead of stopping when it reaches end
+    pub fn wrapping(self) -> Self {
+        Ramp { wrap: true, ..self }
+    }
+}
+
+impl Iterator for Ramp {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        let value = self.next?;
+
+        self.next = match value.checked_add(self.step) {
+            Some(next) if next <= self.end => Some(next),
+            _ if self.wrap => Some(self.start),
+            _ => None,
+        };
+
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_count_from_start_to_end() {
+        let mut ramp = Ramp::new(0x0100, 0x0400, 0x0100);
+
+        assert_eq!(ramp.next(), Some(0x0100));
+        assert_eq!(ramp.next(), Some(0x0200));
+        assert_eq!(ramp.next(), Some(0x0300));
+        assert_eq!(ramp.next(), Some(0x0400));
+        assert_eq!(ramp.next(), None);
+    }
+
+    #[test]
+    fn should_stop_before_passing_end() {
+        let mut ramp = Ramp::new(0x0000, 0x0fff, 0x0800);
+
+        assert_eq!(ramp.next(), Some(0x0000));
+        assert_eq!(ramp.next(), Some(0x0800));
+        assert_eq!(ramp.next(), None);
+    }
+
+    #[test]
+    fn should_limit_to_12bits() {
+        let mut ramp = Ramp::new(0x0ffe, 0xffff, 1);
+
+        assert_eq!(ramp.next(), Some(0x0ffe));
+        assert_eq!(ramp.next(), Some(0x0fff));
+        assert_eq!(ramp.next(), None);
+    }
+
+    #[test]
+    fn should_be_empty_when_start_is_past_end() {
+        assert_eq!(Ramp::new(0x0200, 0x0100, 1).next(), None);
+    }
+
+    #[test]
+    fn should_start_over_when_wrapping() {
+        let mut ramp = Ramp::new(0x0000, 0x0002, 1).wrapping();
+
+        assert_eq!(ramp.next(), Some(0x0000));
+        assert_eq!(ramp.next(), Some(0x0001));
+        assert_eq!(ramp.next(), Some(0x0002));
+        assert_eq!(ramp.next(), Some(0x0000));
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_panic_on_zero_step() {
+        Ramp::new(0x0000, 0x0fff, 0);
+    }
+}
@@ -0,0 +1,43 @@
+//! Two MCP4725 drivers at different addresses sharing a single I2C bus
+
+use core::cell::RefCell;
+use embedded_hal_bus::i2c::RefCellDevice;
+use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+use mcp4725::*;
+
+#[test]
+fn should_share_bus_between_drivers_using_a_proxy() {
+    let bus = RefCell::new(Mock::new(&[
+        Transaction::write(0x60, vec![0x40, 0xff, 0xf0]),
+        Transaction::write(0x61, vec![0x40, 0x80, 0x00]),
+        Transaction::write(0x60, vec![0x00, 0x00]),
+    ]));
+
+    {
+        let mut dac_0 = MCP4725::new(RefCellDevice::new(&bus), 0b000);
+        let mut dac_1 = MCP4725::new(RefCellDevice::new(&bus), 0b001);
+
+        dac_0.set_dac(PowerDown::Normal, 0x0fff).unwrap();
+        dac_1.set_dac(PowerDown::Normal, 0x0800).unwrap();
+        dac_0.set_dac_fast(PowerDown::Normal, 0x0000).unwrap();
+    }
+
+    bus.into_inner().done();
+}
+
+#[test]
+fn should_share_bus_between_drivers_by_borrowing() {
+    let mut i2c = Mock::new(&[
+        Transaction::write(0x60, vec![0x40, 0xff, 0xf0]),
+        Transaction::write(0x61, vec![0x40, 0x80, 0x00]),
+    ]);
+
+    MCP4725::new(&mut i2c, 0b000)
+        .set_dac(PowerDown::Normal, 0x0fff)
+        .unwrap();
+    MCP4725::new(&mut i2c, 0b001)
+        .set_dac(PowerDown::Normal, 0x0800)
+        .unwrap();
+
+    i2c.done();
+}